@@ -29,7 +29,7 @@ impl UseHook for CliUseHook {
         // use foo/bar
         // bar.call()
         // ```
-        let ident = path.split('/').last().unwrap();
+        let ident = path.split('/').next_back().unwrap();
         let Ok(source) = read_to_string(format!("./{}.dom", &path)) else {
             return Ok(None);
         };
@@ -98,9 +98,9 @@ impl BuiltinFn for InputFn {
 
         // Retrieve input
         let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .expect("should be able to read line");
+        if let Err(error) = io::stdin().read_line(&mut input) {
+            return Some(Val::error(error.to_string()));
+        }
 
         // Remove `\n` from `read_line`
         let input = input.trim_end_matches('\n').to_string();
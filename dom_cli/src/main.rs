@@ -2,7 +2,7 @@ mod hooks;
 
 use dom_core::{
     environment::{Env, Val},
-    interpreter::Interpreter,
+    interpreter::{Interpreter, DEFAULT_MAX_DEPTH},
     parser::Parser,
 };
 
@@ -18,12 +18,17 @@ use miette::Result;
 #[derive(clap::Parser)]
 struct Args {
     path: Option<String>,
+    /// The maximum depth of nested function calls
+    #[arg(long, default_value_t = DEFAULT_MAX_DEPTH)]
+    max_depth: usize,
 }
 
-fn result(source: &str, env: &Arc<Mutex<Env>>) -> Result<Val> {
+fn result(source: &str, env: &Arc<Mutex<Env>>, max_depth: usize) -> Result<Val> {
     (|| -> Result<Val> {
         let program = Parser::new(source).produce_ast()?;
-        Interpreter::new::<hooks::CliUseHook, hooks::CliModuleHook>().eval(program, env)
+        Interpreter::new::<hooks::CliUseHook, hooks::CliModuleHook>()
+            .with_max_depth(max_depth)
+            .eval(program, env)
     })()
     .map_err(|error| error.with_source_code(source.to_string()))
 }
@@ -37,7 +42,7 @@ fn main() -> Result<()> {
         // File mode
         Some(path) => {
             let source = read_to_string(path).expect("should be able to read file from path");
-            result(&source, &env).map(|_| ())
+            result(&source, &env, args.max_depth).map(|_| ())
         }
         // Interactive mode
         None => loop {
@@ -50,7 +55,7 @@ fn main() -> Result<()> {
                 .read_line(&mut source)
                 .expect("should be able to read line");
 
-            match result(&source, &env) {
+            match result(&source, &env, args.max_depth) {
                 Ok(result) => print!("{result}"),
                 Err(error) => return Err(error),
             }
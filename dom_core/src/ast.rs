@@ -2,24 +2,32 @@ use std::fmt;
 
 use miette::SourceSpan;
 
-use crate::lexer::RelOp;
+use crate::{lexer::RelOp, stack};
 
 /// An identifier (e.g. a variable name).
 pub(crate) type Ident = String;
 
+/// An argument passed by name, e.g. `verbose = true`.
+pub(crate) type NamedArg = (Ident, Expr);
+
+/// An enum variant, along with the names of its payload fields.
+pub(crate) type Variant = (Ident, Vec<Ident>);
+
 /// The kind of a statement.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Stmt {
     /// A program consisting of a sequence of statements.
     Program { body: Vec<Stmt> },
-    /// A conditional statement.
-    Cond(Cond),
     /// A callable function.
     Func(Func),
-    /// A loop statement.
-    Loop(Loop),
+    /// A struct declaration.
+    Struct(Struct),
+    /// An enum declaration.
+    Enum(Enum),
     /// A variable declaration.
     Var(Var),
+    /// A constant variable declaration.
+    Const(Var),
     /// An expression statement.
     Expr(Expr),
     /// A use statement for modules.
@@ -40,41 +48,214 @@ pub struct Cond {
     pub(crate) condition: Expr,
     /// The body of the conditional to be executed if the condition succeeds.
     pub(crate) body: Vec<Stmt>,
+    /// The branch to be executed if the condition fails, if any.
+    pub(crate) alternate: Option<Box<Alternate>>,
     /// The span of the condition.
     pub(crate) span: SourceSpan,
 }
 
+/// The `else` branch of a conditional.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Alternate {
+    /// An `else if` branch, chaining another conditional.
+    Cond(Cond),
+    /// A final `else` branch.
+    Body {
+        /// The body of the branch.
+        body: Vec<Stmt>,
+        /// The span of the `else` keyword.
+        span: SourceSpan,
+    },
+}
+
 /// A function declaration.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Func {
     /// The identifier of the function.
     pub(crate) ident: Ident,
     /// The parameters of the function.
-    pub(crate) params: Vec<Ident>,
+    pub(crate) params: Vec<Param>,
     /// The body of the function.
     pub(crate) body: Vec<Stmt>,
     /// The span of the function identifier.
     pub(crate) span: SourceSpan,
 }
 
-/// A loop statement.
+/// A struct declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Struct {
+    /// The identifier of the struct.
+    pub(crate) ident: Ident,
+    /// The names of the fields of the struct, in order.
+    pub(crate) fields: Vec<Ident>,
+    /// The span of the struct identifier.
+    pub(crate) span: SourceSpan,
+}
+
+/// An enum declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enum {
+    /// The identifier of the enum.
+    pub(crate) ident: Ident,
+    /// The variants of the enum, along with the names of their payload fields, in order.
+    pub(crate) variants: Vec<Variant>,
+    /// The span of the enum identifier.
+    pub(crate) span: SourceSpan,
+}
+
+/// A loop expression.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Loop {
-    /// The value returned.
+    /// The label of the loop, if any.
+    pub(crate) label: Option<Ident>,
+    /// The body of the loop.
     pub(crate) body: Vec<Stmt>,
     /// The span of the loop keyword.
     pub(crate) span: SourceSpan,
 }
 
+/// A loop expression that runs while a condition holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct While {
+    /// The label of the loop, if any.
+    pub(crate) label: Option<Ident>,
+    /// The condition to be checked before every iteration.
+    pub(crate) condition: Expr,
+    /// The body of the loop.
+    pub(crate) body: Vec<Stmt>,
+    /// The span of the while keyword.
+    pub(crate) span: SourceSpan,
+}
+
+/// A loop expression over the items of an iterable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct For {
+    /// The label of the loop, if any.
+    pub(crate) label: Option<Ident>,
+    /// The identifier each item is bound to.
+    pub(crate) ident: Ident,
+    /// The expression being iterated over.
+    pub(crate) iterable: Expr,
+    /// The body of the loop.
+    pub(crate) body: Vec<Stmt>,
+    /// The span of the for keyword.
+    pub(crate) span: SourceSpan,
+}
+
 /// A variable declaration.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Var {
-    /// The identifier of the variable.
-    pub(crate) ident: Ident,
+    /// The pattern the value is bound to, e.g. an identifier or `[a, b]`.
+    pub(crate) pattern: Pattern,
     /// The value of the variable.
     pub(crate) value: Box<Stmt>,
-    /// The span of the variable identifier.
-    pub(crate) span: SourceSpan,
+}
+
+/// An arm of a match expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    /// The pattern the value is matched against.
+    pub(crate) pattern: Pattern,
+    /// The guard that must also hold for the arm to match, if any.
+    pub(crate) guard: Option<Expr>,
+    /// The body of the arm.
+    pub(crate) body: Vec<Stmt>,
+}
+
+/// A parameter of a function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    /// The pattern the argument is bound to.
+    pub(crate) pattern: Pattern,
+    /// The value used if no argument is passed, if any.
+    pub(crate) default: Option<Expr>,
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern)?;
+
+        if self.default.is_some() {
+            write!(f, " = …")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A pattern that values can be matched against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: SourceSpan,
+}
+
+/// The kind of a pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternKind {
+    /// A wildcard pattern `_`, matching any value.
+    Wildcard,
+    /// A literal pattern, matching values equal to the literal.
+    Literal(Expr),
+    /// A binding pattern, matching any value and binding it to an identifier.
+    Binding(Ident),
+    /// A list pattern, matching lists whose items match the given patterns.
+    List(Vec<Pattern>),
+    /// An enum variant pattern, e.g. `Shape.Circle(radius)`, matching values of the variant whose
+    /// payloads match the given patterns. Without payload patterns, any payload matches.
+    Variant {
+        /// The path to the enum, e.g. `Shape` or `shapes.Shape`.
+        path: Vec<Ident>,
+        /// The identifier of the variant.
+        variant: Ident,
+        /// The patterns for the payload of the variant, if any.
+        payload: Option<Vec<Pattern>>,
+    },
+    /// A rest pattern within a list pattern, e.g. `..rest`, matching any remaining items and
+    /// optionally binding them to an identifier.
+    Rest(Option<Ident>),
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PatternKind::Wildcard => write!(f, "_"),
+            PatternKind::Literal(literal) => write_literal(f, literal),
+            PatternKind::Binding(ident) => write!(f, "{ident}"),
+            PatternKind::List(items) => {
+                let items = items.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "[{}]", items.join(", "))
+            }
+            PatternKind::Variant {
+                path,
+                variant,
+                payload,
+            } => {
+                write!(f, "{}.{variant}", path.join("."))?;
+                if let Some(payload) = payload {
+                    let payload = payload.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    write!(f, "({})", payload.join(", "))?;
+                }
+                Ok(())
+            }
+            PatternKind::Rest(ident) => write!(f, "..{}", ident.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+/// Writes a literal pattern, i.e. a literal or a negated numeric literal.
+fn write_literal(f: &mut fmt::Formatter<'_>, literal: &Expr) -> fmt::Result {
+    match &literal.kind {
+        ExprKind::Int(value) => write!(f, "{value}"),
+        ExprKind::Float(value) => write!(f, "{value}"),
+        ExprKind::Bool(value) => write!(f, "{value}"),
+        ExprKind::Str(value) => write!(f, "{value:?}"),
+        ExprKind::UnaryOp { expr, .. } => {
+            write!(f, "-")?;
+            write_literal(f, expr)
+        }
+        kind => write!(f, "{kind}"),
+    }
 }
 
 /// Logical operators.
@@ -91,6 +272,13 @@ pub enum BinaryOp {
     Sub,
     Mul,
     Div,
+    Rem,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
 }
 
 /// Unary operators.
@@ -102,12 +290,23 @@ pub enum UnaryOp {
 }
 
 /// An expression in the abstract syntax tree.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Expr {
     pub(crate) kind: ExprKind,
     pub(crate) span: SourceSpan,
 }
 
+impl Clone for Expr {
+    fn clone(&self) -> Self {
+        // Chains such as `a + b + c` nest a level deeper with every link, so cloning them is
+        // guarded in the same way as parsing and evaluating them
+        stack::guard(|| Self {
+            kind: self.kind.clone(),
+            span: self.span,
+        })
+    }
+}
+
 /// The kind of expression.
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExprKind {
@@ -118,6 +317,15 @@ pub enum ExprKind {
         /// The value (right-hand side) of the assignment.
         value: Box<Expr>,
     },
+    /// A compound assignment expression, e.g. `foo += 1`.
+    CompoundAssignment {
+        /// The assignee (left-hand side) of the assignment.
+        assignee: Box<Expr>,
+        /// The value (right-hand side) of the assignment.
+        value: Box<Expr>,
+        /// The operation combining the assignee and the value.
+        op: BinaryOp,
+    },
     Pipe {
         left: Box<Expr>,
         right: Box<Expr>,
@@ -125,18 +333,38 @@ pub enum ExprKind {
     Call {
         caller: Box<Expr>,
         args: Vec<Expr>,
+        /// Arguments passed by name, e.g. `verbose = true`, which follow all other arguments.
+        named: Vec<NamedArg>,
     },
     List {
         items: Vec<Expr>,
     },
+    /// A spread expression, e.g. `..items`, expanding an iterable into the surrounding call
+    /// arguments or list items.
+    Spread {
+        /// The iterable being spread.
+        expr: Box<Expr>,
+    },
+    /// A map literal expression.
+    Map {
+        /// The key-value pairs of the map.
+        entries: Vec<(Expr, Expr)>,
+    },
     /// A string expression.
     Str(String),
+    /// An interpolated string expression.
+    Interp {
+        /// The parts of the string, where literal parts are string expressions.
+        parts: Vec<Expr>,
+    },
     /// An identifier expression.
     Ident(Ident),
     /// An boolean literal expression.
     Bool(bool),
     /// An integer literal expression.
     Int(i32),
+    /// A float literal expression.
+    Float(f64),
     /// A relational operation expression.
     RelOp {
         /// The left operand of the comparison operation.
@@ -146,6 +374,15 @@ pub enum ExprKind {
         /// The comparison operation itself.
         op: RelOp,
     },
+    /// A range expression.
+    Range {
+        /// The start of the range.
+        start: Box<Expr>,
+        /// The end of the range.
+        end: Box<Expr>,
+        /// Whether the end of the range is included.
+        inclusive: bool,
+    },
     /// A unary operation expression.
     UnaryOp {
         /// The expression of the unary operation.
@@ -176,10 +413,66 @@ pub enum ExprKind {
         /// The value returned.
         value: Option<Box<Expr>>,
     },
+    /// A throw expression, raising an error.
+    Throw {
+        /// The error thrown.
+        value: Box<Expr>,
+    },
+    /// A try expression, catching errors raised by its body.
+    Try {
+        /// The body that may raise errors.
+        body: Vec<Stmt>,
+        /// The identifier the caught error is bound to, if any.
+        ident: Option<Ident>,
+        /// The body to be executed if an error is caught.
+        catch: Vec<Stmt>,
+    },
     /// A continue expression for loops.
-    Continue,
+    Continue {
+        /// The label of the loop to continue, or the innermost loop if `None`.
+        label: Option<Ident>,
+    },
     /// A break expression for loops.
-    Break,
+    Break {
+        /// The label of the loop to break out of, or the innermost loop if `None`.
+        label: Option<Ident>,
+        /// The value the loop evaluates to, if any.
+        value: Option<Box<Expr>>,
+    },
+    /// An anonymous function expression.
+    Lambda {
+        /// The parameters of the function.
+        params: Vec<Param>,
+        /// The body of the function.
+        body: Vec<Stmt>,
+    },
+    /// An index expression, e.g. `foo[0]` or `foo[0..2]`.
+    Index {
+        /// The expression being indexed.
+        expr: Box<Expr>,
+        /// The index, which may be a range for slices.
+        index: Box<Expr>,
+    },
+    /// A conditional expression.
+    Cond(Box<Cond>),
+    /// A loop expression.
+    Loop(Box<Loop>),
+    /// A loop expression that runs while a condition holds.
+    While(Box<While>),
+    /// A loop expression over the items of an iterable.
+    For(Box<For>),
+    /// A block expression, evaluating to its last statement.
+    Block {
+        /// The body of the block.
+        body: Vec<Stmt>,
+    },
+    /// A match expression.
+    Match {
+        /// The value being matched.
+        scrutinee: Box<Expr>,
+        /// The arms of the match, checked in order.
+        arms: Vec<MatchArm>,
+    },
     /// A module access expression.
     Mod {
         /// The module being accessed.
@@ -193,20 +486,36 @@ impl fmt::Display for ExprKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Assignment { .. } => write!(f, "Assignment"),
+            Self::CompoundAssignment { .. } => write!(f, "CompoundAssignment"),
             Self::Pipe { .. } => write!(f, "Pipe"),
             Self::Call { .. } => write!(f, "Call"),
             Self::List { .. } => write!(f, "List"),
+            Self::Spread { .. } => write!(f, "Spread"),
+            Self::Map { .. } => write!(f, "Map"),
             Self::Str { .. } => write!(f, "Str"),
+            Self::Interp { .. } => write!(f, "Interp"),
             Self::Ident { .. } => write!(f, "Ident"),
             Self::Bool { .. } => write!(f, "Bool"),
             Self::Int { .. } => write!(f, "Int"),
+            Self::Float { .. } => write!(f, "Float"),
             Self::LogicOp { .. } => write!(f, "LogicOp"),
             Self::RelOp { .. } => write!(f, "RelOp"),
+            Self::Range { .. } => write!(f, "Range"),
             Self::UnaryOp { .. } => write!(f, "UnaryOp"),
             Self::BinaryOp { .. } => write!(f, "BinaryOp"),
             Self::Return { .. } => write!(f, "Return"),
-            Self::Continue => write!(f, "Continue"),
-            Self::Break => write!(f, "Break"),
+            Self::Throw { .. } => write!(f, "Throw"),
+            Self::Try { .. } => write!(f, "Try"),
+            Self::Continue { .. } => write!(f, "Continue"),
+            Self::Break { .. } => write!(f, "Break"),
+            Self::Loop(_) => write!(f, "Loop"),
+            Self::While(_) => write!(f, "While"),
+            Self::For(_) => write!(f, "For"),
+            Self::Lambda { .. } => write!(f, "Lambda"),
+            Self::Index { .. } => write!(f, "Index"),
+            Self::Cond(_) => write!(f, "Cond"),
+            Self::Block { .. } => write!(f, "Block"),
+            Self::Match { .. } => write!(f, "Match"),
             Self::Mod { .. } => write!(f, "Mod"),
         }
     }
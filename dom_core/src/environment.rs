@@ -4,11 +4,15 @@ use miette::{Diagnostic, Result, SourceSpan};
 use thiserror::Error;
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
+    ops::Range,
     sync::{Arc, Mutex},
 };
 
-use crate::ast::{Ident, Stmt};
+use crate::{
+    ast::{Ident, Param, Stmt, Variant},
+    stack,
+};
 
 #[derive(Error, Diagnostic, Debug)]
 enum EnvError {
@@ -24,10 +28,18 @@ enum EnvError {
         #[label("this identifier was never defined")]
         span: SourceSpan,
     },
+    #[error("cannot assign twice to constant")]
+    #[diagnostic(code(environment::constant_reassignment))]
+    ConstantReassignment {
+        #[label("cannot assign to this constant")]
+        span: SourceSpan,
+        #[label("constant declared here")]
+        declared: SourceSpan,
+    },
 }
 
 /// Runtime values.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Val {
     /// The identifier of the value (if stored in an environment)
     pub ident: Option<Ident>,
@@ -49,11 +61,50 @@ impl Val {
         kind: ValKind::None,
     };
 
+    /// Creates an error value with the given message, e.g. for a built-in function to raise.
+    pub fn error(message: impl Into<String>) -> Self {
+        ValKind::Error {
+            message: message.into(),
+            code: None,
+            span: None,
+        }
+        .into()
+    }
+
     #[must_use]
     fn with_ident(mut self, ident: Ident) -> Self {
         self.ident = Some(ident);
         self
     }
+
+    /// Consumes the value, returning its kind.
+    pub fn into_kind(mut self) -> ValKind {
+        std::mem::replace(&mut self.kind, ValKind::None)
+    }
+}
+
+// Values can be nested arbitrarily deep, e.g. a list built up in a loop, so walking them is guarded
+// in the same way as walking the syntax tree.
+impl Clone for Val {
+    fn clone(&self) -> Self {
+        stack::guard(|| Self {
+            ident: self.ident.clone(),
+            kind: self.kind.clone(),
+        })
+    }
+}
+
+impl Drop for Val {
+    fn drop(&mut self) {
+        if let ValKind::List(_)
+        | ValKind::Map(_)
+        | ValKind::Instance { .. }
+        | ValKind::Variant { .. } = self.kind
+        {
+            let kind = std::mem::replace(&mut self.kind, ValKind::None);
+            stack::guard(|| drop(kind));
+        }
+    }
 }
 
 /// Value kinds.
@@ -65,33 +116,91 @@ pub enum ValKind {
     Bool(bool),
     /// Integer value.
     Int(i32),
+    /// Float value.
+    Float(f64),
     /// String value.
     Str(String),
-    /// User-defined function.
+    /// User-defined function. Anonymous functions have no identifier.
     Func {
-        ident: Ident,
-        params: Vec<Ident>,
+        ident: Option<Ident>,
+        params: Vec<Param>,
         body: Vec<Stmt>,
         env: Arc<Mutex<Env>>,
     },
     List(Vec<Val>),
+    /// Range of integers, excluding the end. Items are produced lazily when iterated over.
+    Range(Range<i32>),
+    /// Map of string keys to values, ordered by key.
+    Map(BTreeMap<String, Val>),
+    /// User-defined struct type, which can be called to construct instances of it.
+    Struct {
+        ident: Ident,
+        fields: Vec<Ident>,
+    },
+    /// Instance of a user-defined struct, with fields in the order they were declared.
+    Instance {
+        ident: Ident,
+        fields: Vec<(Ident, Val)>,
+    },
+    /// User-defined enum type, whose variants are accessed using `.`.
+    Enum {
+        ident: Ident,
+        variants: Vec<Variant>,
+    },
+    /// Variant of a user-defined enum, along with its payload.
+    Variant {
+        ty: Ident,
+        variant: Ident,
+        payload: Vec<Val>,
+    },
+    /// Error value, either thrown by a script or produced by a failed operation. Errors produced
+    /// by failed operations carry the code and span of their diagnostic.
+    Error {
+        message: String,
+        code: Option<String>,
+        span: Option<SourceSpan>,
+    },
     Mod(Arc<Mutex<Env>>),
 }
 
+impl ValKind {
+    /// Returns a numeric value as a float, promoting integers where needed.
+    pub(crate) fn as_float(&self) -> f64 {
+        match self {
+            ValKind::Int(int) => f64::from(*int),
+            ValKind::Float(float) => *float,
+            _ => unreachable!("only numeric values can be represented as floats"),
+        }
+    }
+}
+
 impl From<Vec<Val>> for Val {
     fn from(value: Vec<Val>) -> Self {
         ValKind::List(value).into()
     }
 }
 
+impl From<BTreeMap<String, Val>> for Val {
+    fn from(value: BTreeMap<String, Val>) -> Self {
+        ValKind::Map(value).into()
+    }
+}
+
 impl std::fmt::Display for Val {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match &self.kind {
+        stack::guard(|| match &self.kind {
             ValKind::None => write!(f, ""),
             ValKind::Bool(bool) => write!(f, "{bool}"),
             ValKind::Int(int) => write!(f, "{int}"),
+            // Whole floats should still be distinguishable from integers, e.g. `1.0`
+            ValKind::Float(float) if float.fract() == 0.0 => write!(f, "{float:.1}"),
+            ValKind::Float(float) => write!(f, "{float}"),
             ValKind::Str(value) => write!(f, "{value}"),
-            ValKind::Func { ident, params, .. } => write!(f, "{ident}({})", params.join(", ")),
+            ValKind::Func { ident, params, .. } => {
+                let ident = ident.as_deref().unwrap_or("fn");
+                let params = params.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "{ident}({})", params.join(", "))
+            }
             ValKind::List(items) => {
                 // We shouldn't use `join` here, since we'd need to map every item
                 // using the `format` macro, and then collect
@@ -104,8 +213,44 @@ impl std::fmt::Display for Val {
                 }
                 write!(f, "]")
             }
+            ValKind::Range(range) => write!(f, "{}..{}", range.start, range.end),
+            ValKind::Map(entries) => {
+                write!(f, "#{{")?;
+                for (idx, (key, value)) in entries.iter().enumerate() {
+                    write!(f, "{key}: {value}")?;
+                    if idx < entries.len() - 1 {
+                        write!(f, ", ")?;
+                    }
+                }
+                write!(f, "}}")
+            }
+            ValKind::Struct { ident, .. } => write!(f, "struct {ident}"),
+            ValKind::Instance { ident, fields } => {
+                write!(f, "{ident} {{ ")?;
+                for (idx, (field, value)) in fields.iter().enumerate() {
+                    write!(f, "{field}: {value}")?;
+                    if idx < fields.len() - 1 {
+                        write!(f, ", ")?;
+                    }
+                }
+                write!(f, " }}")
+            }
+            ValKind::Enum { ident, .. } => write!(f, "enum {ident}"),
+            ValKind::Variant {
+                ty,
+                variant,
+                payload,
+            } => {
+                write!(f, "{ty}.{variant}")?;
+                if !payload.is_empty() {
+                    let payload = payload.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    write!(f, "({})", payload.join(", "))?;
+                }
+                Ok(())
+            }
+            ValKind::Error { message, .. } => write!(f, "{message}"),
             ValKind::Mod(_) => write!(f, "{}", self.ident.as_ref().unwrap()),
-        }
+        })
     }
 }
 
@@ -142,6 +287,8 @@ pub struct Env {
     parent: Option<Arc<Mutex<Env>>>,
     /// The values stored in this environment.
     values: HashMap<String, Val>,
+    /// The spans of constant declarations in this environment.
+    constants: HashMap<String, SourceSpan>,
     builtins: Arc<Mutex<BuiltinRegistry>>,
 }
 
@@ -161,6 +308,7 @@ impl Env {
         Arc::new(Mutex::new(Self {
             parent: Some(Arc::clone(parent)),
             values: HashMap::new(),
+            constants: HashMap::new(),
             builtins: Arc::clone(builtins),
         }))
     }
@@ -171,6 +319,7 @@ impl Env {
         Arc::new(Mutex::new(Self {
             parent: None,
             values: HashMap::new(),
+            constants: HashMap::new(),
             builtins,
         }))
     }
@@ -235,22 +384,39 @@ impl Env {
         Ok(value)
     }
 
+    /// Declares a new constant with the given name and value.
+    ///
+    /// Returns an error if a variable with the same name already exists in this environment.
+    pub fn declare_const(&mut self, name: &str, value: Val, span: SourceSpan) -> Result<Val> {
+        let value = self.declare(name, value, span)?;
+        self.constants.insert(name.to_string(), span);
+        Ok(value)
+    }
+
     /// Declares a new variable with the given name and value, overwritting any variable that
     /// might exist.
     ///
     /// Does not return anything.
     pub fn declare_unchecked(&mut self, name: &str, value: Val) {
+        self.constants.remove(name);
         self.values
             .insert(name.to_string(), value.with_ident(name.to_string()));
     }
 
     /// Assigns a new value to the variable with the given name.
     ///
-    /// Returns an error if no variable with the given name exists in this environment or its parents.
+    /// Returns an error if no variable with the given name exists in this environment or its
+    /// parents, or if the variable is a constant.
     pub fn assign(env: &Arc<Mutex<Self>>, name: &str, value: Val, span: SourceSpan) -> Result<Val> {
         // Find the environment where the variable is declared.
         let env = Self::resolve(env, name, span)?;
-        let values = &mut env.lock().unwrap().values;
+        let mut env = env.lock().unwrap();
+
+        if let Some(&declared) = env.constants.get(name) {
+            return Err(EnvError::ConstantReassignment { span, declared }.into());
+        }
+
+        let values = &mut env.values;
 
         let value = value.with_ident(name.to_string());
 
@@ -347,7 +513,7 @@ mod tests {
             .expect("should be able to declare variable");
 
         // Lookup the variable
-        let result = Env::lookup(&env, &name, span).expect("variable should exist");
+        let result = Env::lookup(&env, name, span).expect("variable should exist");
         assert_eq!(result, value);
     }
 
@@ -383,7 +549,7 @@ mod tests {
         let name = "foo";
         let span = (0, 3).into();
 
-        let result = Env::lookup(&env, &name, span).expect_err("result should be an error");
+        let result = Env::lookup(&env, name, span).expect_err("result should be an error");
 
         assert!(matches!(
             result.downcast_ref::<EnvError>(),
@@ -411,10 +577,35 @@ mod tests {
             .expect("should be able to assign value to variable");
 
         // Lookup the variable
-        let result = Env::lookup(&env, &name, span).expect("should be able to lookup variable");
+        let result = Env::lookup(&env, name, span).expect("should be able to lookup variable");
         assert_eq!(result, value);
     }
 
+    #[test]
+    fn assign_const_error() {
+        let env = Env::new();
+
+        let name = "foo";
+        let value: Val = ValKind::Int(0).into();
+        let span = (0, 3).into();
+
+        // Declare a constant in the environment
+        env.lock()
+            .unwrap()
+            .declare_const(name, value.clone(), span)
+            .expect("should be able to declare constant");
+
+        // Attempt to assign a new value to the constant from a child environment
+        let child_env = Env::with_parent(&env);
+        let result = Env::assign(&child_env, name, value, (4, 3).into())
+            .expect_err("result should be an error");
+
+        assert!(matches!(
+            result.downcast_ref::<EnvError>(),
+            Some(EnvError::ConstantReassignment { .. })
+        ));
+    }
+
     #[test]
     fn nested_environments() {
         let parent_env = Env::new();
@@ -434,7 +625,7 @@ mod tests {
         let child_env = Env::with_parent(&Arc::clone(&parent_env));
 
         // Lookup the variable from the child environment
-        let result = Env::lookup(&child_env, &name, span);
+        let result = Env::lookup(&child_env, name, span);
         assert_eq!(result.unwrap(), value.clone());
 
         // Declare a new variable in the parent environment
@@ -448,7 +639,7 @@ mod tests {
 
         // Lookup the new variable from the child environment
         let result =
-            Env::lookup(&child_env, &name, span).expect("should be able to lookup variable");
+            Env::lookup(&child_env, name, span).expect("should be able to lookup variable");
         assert_eq!(result, value);
     }
 }
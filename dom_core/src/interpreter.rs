@@ -1,14 +1,181 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    ops::{ControlFlow, Range},
+    sync::{Arc, Mutex},
+};
 
 use miette::{Diagnostic, ErrReport, Result, SourceSpan};
 use thiserror::Error;
 
 use crate::{
-    ast::{BinaryOp, Cond, Expr, ExprKind, Func, Ident, LogicOp, Loop, Stmt, UnaryOp, Use, Var},
+    ast::{
+        Alternate, BinaryOp, Cond, Enum, Expr, ExprKind, For, Func, Ident, LogicOp, Loop, MatchArm,
+        Param, Pattern, PatternKind, Stmt, Struct, UnaryOp, Use, Var, Variant, While,
+    },
     environment::{Env, Val, ValKind},
     lexer::RelOp,
+    stack,
 };
 
+/// A variable bound by a pattern, along with the span of its binding.
+type Binding = (Ident, Val, SourceSpan);
+
+/// An argument passed by name, along with the span of its value.
+type NamedArg = (Ident, Val, SourceSpan);
+
+/// The default maximum depth of nested function calls.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// A user-defined function being called.
+struct Callee {
+    ident: Option<Ident>,
+    params: Vec<Param>,
+    body: Vec<Stmt>,
+    env: Arc<Mutex<Env>>,
+}
+
+/// The target of an assignment, with its indexes already evaluated.
+enum Place {
+    /// A variable.
+    Ident { ident: Ident, span: SourceSpan },
+    /// An item of a list or map, e.g. `foo[0]`.
+    Index {
+        container: Box<Place>,
+        /// The span of the container expression.
+        span: SourceSpan,
+        index: ValKind,
+        index_span: SourceSpan,
+    },
+    /// A field of a struct instance, e.g. `foo.bar`.
+    Field {
+        instance: Box<Place>,
+        item: Expr,
+        /// The span of the whole field access.
+        span: SourceSpan,
+    },
+}
+
+/// The result of evaluating an expression in tail position of a function.
+enum Tail {
+    /// The expression evaluated to a value.
+    Value(Val),
+    /// The expression is a call to the function itself, with the given arguments.
+    Call(Vec<Val>, Vec<NamedArg>),
+}
+
+impl Place {
+    /// Reads the current value of the place.
+    fn read(&self, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        match self {
+            Place::Ident { ident, span } => Env::lookup(env, ident, *span),
+            Place::Index {
+                container,
+                span,
+                index,
+                index_span,
+            } => index_value(
+                container.read(env)?.into_kind(),
+                index.clone(),
+                *span,
+                *index_span,
+            ),
+            Place::Field {
+                instance,
+                item,
+                span,
+            } => {
+                let ValKind::Instance { ident, fields } = instance.read(env)?.into_kind() else {
+                    return Err(
+                        InterpreterError::InvalidAssignmentIdentifier { span: *span }.into(),
+                    );
+                };
+
+                let field = field_name(&ident, item)?;
+
+                match fields.into_iter().find(|(name, _)| *name == field) {
+                    Some((_, value)) => Ok(value),
+                    None => Err(InterpreterError::UnknownField {
+                        ident,
+                        field,
+                        span: item.span,
+                    }
+                    .into()),
+                }
+            }
+        }
+    }
+
+    /// Assigns `value` to the place.
+    ///
+    /// Indexed containers and instances are updated in place by assigning the updated container
+    /// to its own place, all the way back to the variable holding it.
+    fn write(self, value: Val, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        match self {
+            Place::Ident { ident, span } => Env::assign(env, &ident, value, span),
+            Place::Index {
+                container,
+                span,
+                index,
+                index_span,
+            } => {
+                let updated = match (container.read(env)?.into_kind(), index) {
+                    (ValKind::List(mut items), ValKind::Int(index)) => {
+                        let Some(idx) = wrap_index(index, items.len()) else {
+                            return Err(InterpreterError::IndexOutOfBounds {
+                                span: index_span,
+                                index,
+                                len: items.len(),
+                            }
+                            .into());
+                        };
+                        items[idx] = value.clone();
+                        ValKind::List(items)
+                    }
+                    (ValKind::Map(mut entries), ValKind::Str(key)) => {
+                        entries.insert(key, value.clone());
+                        ValKind::Map(entries)
+                    }
+                    (ValKind::List(_) | ValKind::Map(_), _) => {
+                        return Err(InterpreterError::InvalidIndex { span: index_span }.into())
+                    }
+                    _ => return Err(InterpreterError::NotIndexable { span }.into()),
+                };
+
+                container.write(updated.into(), env)?;
+
+                Ok(value)
+            }
+            Place::Field {
+                instance,
+                item,
+                span,
+            } => {
+                let ValKind::Instance { ident, mut fields } = instance.read(env)?.into_kind()
+                else {
+                    return Err(InterpreterError::InvalidAssignmentIdentifier { span }.into());
+                };
+
+                let field = field_name(&ident, &item)?;
+
+                let Some((_, old)) = fields.iter_mut().find(|(name, _)| *name == field) else {
+                    return Err(InterpreterError::UnknownField {
+                        ident,
+                        field,
+                        span: item.span,
+                    }
+                    .into());
+                };
+                *old = value.clone();
+
+                instance.write(ValKind::Instance { ident, fields }.into(), env)?;
+
+                Ok(value)
+            }
+        }
+    }
+}
+
 #[derive(Error, Diagnostic, Debug)]
 pub enum InterpreterError {
     #[error("assignment does not contain valid identifier")]
@@ -34,13 +201,12 @@ pub enum InterpreterError {
         right: ExprKind,
         op: BinaryOp,
     },
-    #[error("logical operation `{op:?}` unsupported for types `{left}` and `{right}`")]
-    #[diagnostic(code(interpreter::logical_expression_unsupported))]
-    LogicalExpressionUnsupported {
-        #[label("this operation is unsupported")]
+    #[error("logical operation `{op:?}` expects booleans, found `{value}`")]
+    #[diagnostic(code(interpreter::non_bool_operand))]
+    NonBoolOperand {
+        #[label("this operand is not a boolean")]
         span: SourceSpan,
-        left: ExprKind,
-        right: ExprKind,
+        value: Val,
         op: LogicOp,
     },
     #[error("relational operation `{op:?}` unsupported for types `{left}` and `{right}`")]
@@ -64,10 +230,198 @@ pub enum InterpreterError {
         #[label("this is not a function call")]
         span: SourceSpan,
     },
-    #[error("caller arguments do not match function arguments")]
+    #[error("caller arguments do not match function parameters")]
     #[diagnostic(code(interpreter::mismatched_args))]
     MismatchedArgs {
-        #[label("this call has incorrect argument count")]
+        #[label("this call has incorrect arguments, expected `({expected})`")]
+        span: SourceSpan,
+        expected: String,
+    },
+    #[error("maximum call depth of {depth} exceeded")]
+    #[diagnostic(
+        code(interpreter::stack_overflow),
+        help("the calls leading here were: {chain}")
+    )]
+    StackOverflow {
+        depth: usize,
+        chain: String,
+        #[label("this call exceeds the maximum call depth")]
+        span: SourceSpan,
+    },
+    #[error("no parameter named `{ident}`")]
+    #[diagnostic(code(interpreter::unknown_arg))]
+    UnknownArg {
+        ident: Ident,
+        #[label("expected one of `({expected})`")]
+        span: SourceSpan,
+        expected: String,
+    },
+    #[error("argument `{ident}` is passed more than once")]
+    #[diagnostic(code(interpreter::duplicate_arg))]
+    DuplicateArg {
+        ident: Ident,
+        /// How the argument was first passed, i.e. `by position` or `by name`.
+        passed: &'static str,
+        #[label("this argument has already been passed {passed}")]
+        span: SourceSpan,
+    },
+    #[error("cannot `{keyword}` out of a function")]
+    #[diagnostic(
+        code(interpreter::jump_out_of_function),
+        help("`break` and `continue` only apply to loops within the same function")
+    )]
+    JumpOutOfFunction {
+        keyword: &'static str,
+        #[label("this `{keyword}` is not inside a loop in this function")]
+        span: SourceSpan,
+    },
+    #[error("built-in functions do not take named arguments")]
+    #[diagnostic(code(interpreter::named_builtin_args))]
+    NamedBuiltinArgs {
+        #[label("this argument is named")]
+        span: SourceSpan,
+    },
+    #[error("condition does not evaluate to a boolean")]
+    #[diagnostic(code(interpreter::non_bool_condition))]
+    NonBoolCondition {
+        #[label("this condition is not a boolean")]
+        span: SourceSpan,
+    },
+    #[error("expression is not iterable")]
+    #[diagnostic(
+        code(interpreter::not_iterable),
+        help("only lists, strings and ranges can be iterated over")
+    )]
+    NotIterable {
+        #[label("this expression cannot be iterated over")]
+        span: SourceSpan,
+    },
+    #[error("range bounds must be integers")]
+    #[diagnostic(code(interpreter::invalid_range_bound))]
+    InvalidRangeBound {
+        #[label("this bound is not a valid integer")]
+        span: SourceSpan,
+    },
+    #[error("map keys must be strings")]
+    #[diagnostic(code(interpreter::invalid_map_key))]
+    InvalidMapKey {
+        #[label("this key is not a string")]
+        span: SourceSpan,
+    },
+    #[error("division by zero")]
+    #[diagnostic(code(interpreter::division_by_zero))]
+    DivisionByZero {
+        #[label("this divisor is zero")]
+        span: SourceSpan,
+    },
+    #[error("shift amount `{amount}` is invalid")]
+    #[diagnostic(
+        code(interpreter::invalid_shift),
+        help("shift amounts must be between 0 and 31")
+    )]
+    InvalidShift {
+        #[label("this shift amount is out of range")]
+        span: SourceSpan,
+        amount: i32,
+    },
+    #[error("integer overflow")]
+    #[diagnostic(code(interpreter::integer_overflow))]
+    IntegerOverflow {
+        #[label("this operation overflows")]
+        span: SourceSpan,
+    },
+    #[error("expression cannot be indexed")]
+    #[diagnostic(
+        code(interpreter::not_indexable),
+        help("only lists, strings and maps can be indexed")
+    )]
+    NotIndexable {
+        #[label("this expression cannot be indexed")]
+        span: SourceSpan,
+    },
+    #[error("index has an invalid type")]
+    #[diagnostic(
+        code(interpreter::invalid_index),
+        help("lists and strings are indexed by integers or ranges, and maps by strings")
+    )]
+    InvalidIndex {
+        #[label("this index has an invalid type")]
+        span: SourceSpan,
+    },
+    #[error("index `{index}` is out of bounds for length `{len}`")]
+    #[diagnostic(code(interpreter::index_out_of_bounds))]
+    IndexOutOfBounds {
+        #[label("this index is out of bounds")]
+        span: SourceSpan,
+        index: i32,
+        len: usize,
+    },
+    #[error("slice `{start}..{end}` is out of bounds for length `{len}`")]
+    #[diagnostic(code(interpreter::slice_out_of_bounds))]
+    SliceOutOfBounds {
+        #[label("this slice is out of bounds")]
+        span: SourceSpan,
+        start: i32,
+        end: i32,
+        len: usize,
+    },
+    #[error("key `{key}` not found")]
+    #[diagnostic(code(interpreter::key_not_found))]
+    KeyNotFound {
+        #[label("this key is not in the map")]
+        span: SourceSpan,
+        key: String,
+    },
+    #[error("no match arm matches the value")]
+    #[diagnostic(
+        code(interpreter::no_matching_arm),
+        help("consider adding a wildcard arm `_ => ..` to match any value")
+    )]
+    NoMatchingArm {
+        #[label("this value is not matched by any arm")]
+        span: SourceSpan,
+    },
+    #[error("value `{value}` does not match pattern `{pattern}`")]
+    #[diagnostic(code(interpreter::pattern_mismatch))]
+    PatternMismatch {
+        value: String,
+        pattern: String,
+        #[label("this pattern does not match")]
+        span: SourceSpan,
+        #[label("this value has a different shape")]
+        value_span: SourceSpan,
+    },
+    #[error("struct `{ident}` has no field `{field}`")]
+    #[diagnostic(code(interpreter::unknown_field))]
+    UnknownField {
+        ident: Ident,
+        field: String,
+        #[label("this field does not exist")]
+        span: SourceSpan,
+    },
+    #[error("enum `{ident}` has no variant `{variant}`")]
+    #[diagnostic(code(interpreter::unknown_variant))]
+    UnknownVariant {
+        ident: Ident,
+        variant: String,
+        #[label("this variant does not exist")]
+        span: SourceSpan,
+    },
+    #[error("variant `{variant}` requires a payload")]
+    #[diagnostic(
+        code(interpreter::missing_payload),
+        help("this variant should be called with {len} argument(s)")
+    )]
+    MissingPayload {
+        variant: Ident,
+        len: usize,
+        #[label("this variant is missing its payload")]
+        span: SourceSpan,
+    },
+    #[error("expression is not an enum")]
+    #[diagnostic(code(interpreter::not_an_enum))]
+    NotAnEnum {
+        #[label("this does not refer to an enum")]
         span: SourceSpan,
     },
     #[error("module not found")]
@@ -79,7 +433,7 @@ pub enum InterpreterError {
     #[error("expression is not a valid module")]
     #[diagnostic(code(interpreter::invalid_module))]
     InvalidModule {
-        #[label("this expression is not a module")]
+        #[label("this expression is not a module or struct instance")]
         span: SourceSpan,
     },
 }
@@ -95,11 +449,26 @@ impl From<ErrReport> for InterpreterError {
 #[derive(Error, Diagnostic, Debug)]
 pub enum Exception {
     #[error("cannot break out of non-loop")]
-    Break,
+    Break {
+        label: Option<Ident>,
+        value: Val,
+        #[label("this `break` is not inside a loop")]
+        span: SourceSpan,
+    },
     #[error("cannot continue out of non-loop")]
-    Continue,
+    Continue {
+        label: Option<Ident>,
+        #[label("this `continue` is not inside a loop")]
+        span: SourceSpan,
+    },
     #[error("cannot return out of non-func")]
-    Return(Option<Box<Expr>>),
+    Return(Val),
+    #[error("uncaught error: {value}")]
+    Throw {
+        value: Val,
+        #[label("this error was never caught")]
+        span: SourceSpan,
+    },
 }
 
 pub trait UseHook {
@@ -118,6 +487,10 @@ pub trait ModuleHook {
 pub struct Interpreter {
     use_hook: Box<dyn UseHook>,
     module_hook: Box<dyn ModuleHook>,
+    /// The maximum depth of nested function calls.
+    max_depth: usize,
+    /// The identifiers of the functions currently being called, outermost first.
+    frames: RefCell<Vec<Ident>>,
 }
 
 impl Interpreter {
@@ -125,15 +498,30 @@ impl Interpreter {
         Self {
             use_hook: Box::new(U::default()),
             module_hook: Box::new(M::default()),
+            max_depth: DEFAULT_MAX_DEPTH,
+            frames: RefCell::default(),
         }
     }
 
+    /// Sets the maximum depth of nested function calls, beyond which calls fail with a stack
+    /// overflow error.
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
     pub fn eval(&self, statement: impl Into<Stmt>, env: &Arc<Mutex<Env>>) -> Result<Val> {
-        match statement.into() {
+        let statement = statement.into();
+
+        // Nested statements and expressions are evaluated through here, so this is where running
+        // out of stack is guarded against
+        stack::guard(|| self.eval_stmt(statement, env))
+    }
+
+    fn eval_stmt(&self, statement: Stmt, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        match statement {
             Stmt::Program { body } => self.eval_body(body, env),
-            Stmt::Cond(Cond {
-                condition, body, ..
-            }) => self.eval_cond(condition, body, env),
             Stmt::Func(Func {
                 ident,
                 params,
@@ -141,23 +529,51 @@ impl Interpreter {
                 span,
                 ..
             }) => self.eval_func(&ident, params, body, env, span),
-            Stmt::Loop(Loop { body, .. }) => self.eval_loop(&body, env),
-            Stmt::Var(Var { ident, value, span }) => self.eval_var(&ident, *value, env, span),
+            Stmt::Struct(Struct {
+                ident,
+                fields,
+                span,
+            }) => self.eval_struct(ident, fields, env, span),
+            Stmt::Enum(Enum {
+                ident,
+                variants,
+                span,
+            }) => self.eval_enum(ident, variants, env, span),
+            Stmt::Var(var) => self.eval_var(var, env, Env::declare),
+            Stmt::Const(var) => self.eval_var(var, env, Env::declare_const),
             Stmt::Expr(expr) => {
                 let Expr { kind, span } = expr;
                 match kind {
                     ExprKind::Assignment { assignee, value } => {
                         self.eval_assign(*assignee, *value, env)
                     }
+                    ExprKind::CompoundAssignment {
+                        assignee,
+                        value,
+                        op,
+                    } => self.eval_compound_assign(*assignee, *value, op, span, env),
                     ExprKind::Pipe { left, right } => self.eval_pipe_expr(*left, *right, env),
-                    ExprKind::Call { caller, args } => self.eval_call(*caller, args, env, span),
+                    ExprKind::Call {
+                        caller,
+                        args,
+                        named,
+                    } => self.eval_call(*caller, args, named, env, span),
                     ExprKind::List { items } => self.eval_list_expr(items, env),
+                    ExprKind::Spread { .. } => {
+                        unreachable!("spreads should only occur within calls and lists")
+                    }
+                    ExprKind::Map { entries } => self.eval_map_expr(entries, env),
                     ExprKind::LogicOp { left, right, op } => {
-                        self.eval_logic_expr(*left, *right, op, span, env)
+                        self.eval_logic_expr(*left, *right, op, env)
                     }
                     ExprKind::RelOp { left, right, op } => {
                         self.eval_rel_expr(*left, *right, op, span, env)
                     }
+                    ExprKind::Range {
+                        start,
+                        end,
+                        inclusive,
+                    } => self.eval_range_expr(*start, *end, inclusive, env),
                     ExprKind::UnaryOp { expr, op } => self.eval_unary_expr(*expr, op, span, env),
                     ExprKind::BinaryOp { left, right, op } => {
                         self.eval_binary_expr(*left, *right, op, span, env)
@@ -165,10 +581,38 @@ impl Interpreter {
                     ExprKind::Ident(ident) => self.eval_ident(&ident, env, span),
                     ExprKind::Bool(value) => Ok(ValKind::Bool(value).into()),
                     ExprKind::Int(number) => Ok(ValKind::Int(number).into()),
+                    ExprKind::Float(number) => Ok(ValKind::Float(number).into()),
                     ExprKind::Str(value) => Ok(ValKind::Str(value).into()),
-                    ExprKind::Return { value } => Err(Exception::Return(value).into()),
-                    ExprKind::Continue => Err(Exception::Continue.into()),
-                    ExprKind::Break => Err(Exception::Break.into()),
+                    ExprKind::Interp { parts } => self.eval_interp_expr(parts, env),
+                    ExprKind::Return { value } => {
+                        // The value is evaluated here, while any variables it refers to are
+                        // still in scope
+                        let value = match value {
+                            Some(value) => self.eval(*value, env)?,
+                            None => Val::NONE,
+                        };
+                        Err(Exception::Return(value).into())
+                    }
+                    ExprKind::Continue { label } => Err(Exception::Continue { label, span }.into()),
+                    ExprKind::Break { label, value } => {
+                        let value = match value {
+                            Some(value) => self.eval(*value, env)?,
+                            None => Val::NONE,
+                        };
+                        Err(Exception::Break { label, value, span }.into())
+                    }
+                    ExprKind::Loop(node) => self.eval_loop(*node, env),
+                    ExprKind::While(node) => self.eval_while(*node, env),
+                    ExprKind::For(node) => self.eval_for(*node, env),
+                    ExprKind::Lambda { params, body } => Ok(self.eval_lambda(params, body, env)),
+                    ExprKind::Index { expr, index } => self.eval_index_expr(*expr, *index, env),
+                    ExprKind::Cond(cond) => self.eval_cond(*cond, env),
+                    ExprKind::Block { body } => self.eval_body(body, &Env::with_parent(env)),
+                    ExprKind::Match { scrutinee, arms } => {
+                        self.eval_match_expr(*scrutinee, arms, env)
+                    }
+                    ExprKind::Throw { value } => self.eval_throw(*value, env, span),
+                    ExprKind::Try { body, ident, catch } => self.eval_try(body, ident, catch, env),
                     ExprKind::Mod { module, item } => self.eval_mod_expr(*module, *item, env),
                 }
             }
@@ -184,105 +628,295 @@ impl Interpreter {
         Ok(last)
     }
 
-    fn eval_cond(&self, condition: Expr, body: Vec<Stmt>, env: &Arc<Mutex<Env>>) -> Result<Val> {
-        let ValKind::Bool(success) = self.eval(condition, env)?.kind else {
-            unreachable!("`Val::Bool` should be returned from condition evaluation");
-        };
+    fn eval_cond(&self, cond: Cond, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        let Cond {
+            condition,
+            body,
+            alternate,
+            ..
+        } = cond;
 
-        if success {
+        if self.eval_condition(condition, env)? {
             let env = Env::with_parent(env);
             let result = self.eval_body(body, &env)?;
             return Ok(result);
         }
 
-        Ok(Val::NONE)
+        match alternate.map(|alternate| *alternate) {
+            // Chains of `else if` nest without passing through `Self::eval`
+            Some(Alternate::Cond(cond)) => stack::guard(|| self.eval_cond(cond, env)),
+            Some(Alternate::Body { body, .. }) => {
+                let env = Env::with_parent(env);
+                self.eval_body(body, &env)
+            }
+            None => Ok(Val::NONE),
+        }
+    }
+
+    fn eval_condition(&self, condition: Expr, env: &Arc<Mutex<Env>>) -> Result<bool> {
+        let span = condition.span;
+
+        let ValKind::Bool(success) = self.eval(condition, env)?.kind else {
+            return Err(InterpreterError::NonBoolCondition { span }.into());
+        };
+
+        Ok(success)
     }
 
     fn eval_func(
         &self,
         ident: &Ident,
-        params: Vec<Ident>,
+        params: Vec<Param>,
         body: Vec<Stmt>,
         env: &Arc<Mutex<Env>>,
         span: SourceSpan,
     ) -> Result<Val> {
         let func = ValKind::Func {
-            ident: ident.to_owned(),
+            ident: Some(ident.to_owned()),
             params,
             body,
-            env: Env::with_parent(env),
+            // Functions capture the environment they are defined in
+            env: Arc::clone(env),
         };
 
         env.lock().unwrap().declare(ident, func.into(), span)
     }
 
-    fn eval_loop(&self, body: &Vec<Stmt>, env: &Arc<Mutex<Env>>) -> Result<Val> {
-        let mut last = None;
+    fn eval_struct(
+        &self,
+        ident: Ident,
+        fields: Vec<Ident>,
+        env: &Arc<Mutex<Env>>,
+        span: SourceSpan,
+    ) -> Result<Val> {
+        let name = ident.clone();
+        let value = ValKind::Struct { ident, fields };
+
+        env.lock().unwrap().declare(&name, value.into(), span)
+    }
+
+    fn eval_enum(
+        &self,
+        ident: Ident,
+        variants: Vec<Variant>,
+        env: &Arc<Mutex<Env>>,
+        span: SourceSpan,
+    ) -> Result<Val> {
+        let name = ident.clone();
+        let value = ValKind::Enum { ident, variants };
+
+        env.lock().unwrap().declare(&name, value.into(), span)
+    }
+
+    fn eval_lambda(&self, params: Vec<Param>, body: Vec<Stmt>, env: &Arc<Mutex<Env>>) -> Val {
+        ValKind::Func {
+            ident: None,
+            params,
+            body,
+            env: Arc::clone(env),
+        }
+        .into()
+    }
+
+    fn eval_loop(&self, node: Loop, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        let Loop { label, body, .. } = node;
 
-        'outer: loop {
+        loop {
             let loop_env = Env::with_parent(env);
 
-            for stmt in body {
-                let result = self.eval(stmt.clone(), &loop_env);
+            if let ControlFlow::Break(value) =
+                self.eval_iteration(label.as_deref(), &body, &loop_env)?
+            {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn eval_while(&self, node: While, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        let While {
+            label,
+            condition,
+            body,
+            ..
+        } = node;
+
+        while self.eval_condition(condition.clone(), env)? {
+            let loop_env = Env::with_parent(env);
 
-                match result {
-                    Ok(result) => last = Some(result),
-                    Err(kind) => match kind.downcast_ref() {
-                        Some(Exception::Continue) => continue 'outer,
-                        Some(Exception::Break) => break 'outer,
-                        _ => return Err(kind),
-                    },
-                }
+            if let ControlFlow::Break(value) =
+                self.eval_iteration(label.as_deref(), &body, &loop_env)?
+            {
+                return Ok(value);
             }
         }
 
-        match last {
-            Some(val) => Ok(val),
-            None => Ok(Val::NONE),
+        Ok(Val::NONE)
+    }
+
+    fn eval_for(&self, node: For, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        let For {
+            label,
+            ident,
+            iterable,
+            body,
+            ..
+        } = node;
+
+        let span = iterable.span;
+        let items = iterate(self.eval(iterable, env)?, span)?;
+
+        for item in items {
+            // Every iteration gets a fresh environment
+            let loop_env = Env::with_parent(env);
+            loop_env.lock().unwrap().declare_unchecked(&ident, item);
+
+            if let ControlFlow::Break(value) =
+                self.eval_iteration(label.as_deref(), &body, &loop_env)?
+            {
+                return Ok(value);
+            }
+        }
+
+        Ok(Val::NONE)
+    }
+
+    /// Evaluates a single iteration of the body of a loop with label `label`, handling `break`
+    /// and `continue`. Breaking out of the loop yields the value it evaluates to.
+    ///
+    /// Labeled jumps targeting an outer loop are passed through.
+    fn eval_iteration(
+        &self,
+        label: Option<&str>,
+        body: &[Stmt],
+        env: &Arc<Mutex<Env>>,
+    ) -> Result<ControlFlow<Val>> {
+        let targets = |target: &Option<Ident>| target.is_none() || target.as_deref() == label;
+
+        for stmt in body {
+            let Err(report) = self.eval(stmt.clone(), env) else {
+                continue;
+            };
+
+            match report.downcast::<Exception>() {
+                Ok(Exception::Continue { label: target, .. }) if targets(&target) => {
+                    return Ok(ControlFlow::Continue(()));
+                }
+                Ok(Exception::Break {
+                    label: target,
+                    value,
+                    ..
+                }) if targets(&target) => return Ok(ControlFlow::Break(value)),
+                Ok(exception) => return Err(exception.into()),
+                Err(report) => return Err(report),
+            }
         }
+
+        Ok(ControlFlow::Continue(()))
     }
 
+    /// Evaluates a variable declaration, declaring each binding in its pattern using `declare`.
     fn eval_var(
         &self,
-        ident: &str,
-        value: Stmt,
+        var: Var,
         env: &Arc<Mutex<Env>>,
-        span: SourceSpan,
+        declare: fn(&mut Env, &str, Val, SourceSpan) -> Result<Val>,
     ) -> Result<Val> {
-        let value = self.eval(value, env)?;
-        let result = env.lock().unwrap().declare(ident, value, span)?;
-        Ok(result)
+        let Var { pattern, value } = var;
+
+        let value_span = match value.as_ref() {
+            Stmt::Expr(expr) => expr.span,
+            _ => pattern.span,
+        };
+
+        let value = self.eval(*value, env)?;
+
+        let bindings = self.destructure(&pattern, value.clone(), value_span, env)?;
+
+        let mut env = env.lock().unwrap();
+        for (ident, value, span) in bindings {
+            let declared = declare(&mut env, &ident, value, span)?;
+
+            // Simple declarations evaluate to the declared variable itself
+            if let PatternKind::Binding(_) = pattern.kind {
+                return Ok(declared);
+            }
+        }
+
+        Ok(value)
     }
 
     fn eval_assign(&self, assignee: Expr, value: Expr, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        let value = self.eval(value, env)?;
+        self.eval_place(assignee, env)?.write(value, env)
+    }
+
+    /// Evaluates a compound assignment, e.g. `foo[0] += 1`, where the assignee is only evaluated
+    /// once.
+    fn eval_compound_assign(
+        &self,
+        assignee: Expr,
+        value: Expr,
+        op: BinaryOp,
+        span: SourceSpan,
+        env: &Arc<Mutex<Env>>,
+    ) -> Result<Val> {
+        let place = self.eval_place(assignee.clone(), env)?;
+
+        let lhs = place.read(env)?.into_kind();
+        let rhs = self.eval(value.clone(), env)?.into_kind();
+        let result = binary_op(lhs, rhs, op, &assignee, &value, span)?;
+
+        place.write(result, env)
+    }
+
+    /// Evaluates the indexes of `assignee`, which is either an identifier, or an index or field
+    /// of another assignee, e.g. `foo[0].bar`.
+    fn eval_place(&self, assignee: Expr, env: &Arc<Mutex<Env>>) -> Result<Place> {
         let span = assignee.span;
 
-        let ExprKind::Ident(assignee) = assignee.kind else {
-            return Err(InterpreterError::InvalidAssignmentIdentifier { span }.into());
-        };
+        match assignee.kind {
+            ExprKind::Ident(ident) => Ok(Place::Ident { ident, span }),
+            ExprKind::Index { expr, index } => {
+                let (span, index_span) = (expr.span, index.span);
 
-        let value = self.eval(value, env)?;
-        let result = Env::assign(env, &assignee, value, span)?;
-        Ok(result)
+                Ok(Place::Index {
+                    container: Box::new(self.eval_place(*expr, env)?),
+                    span,
+                    index: self.eval(*index, env)?.into_kind(),
+                    index_span,
+                })
+            }
+            ExprKind::Mod { module, item } => Ok(Place::Field {
+                instance: Box::new(self.eval_place(*module, env)?),
+                item: *item,
+                span,
+            }),
+            _ => Err(InterpreterError::InvalidAssignmentIdentifier { span }.into()),
+        }
     }
 
     fn eval_pipe_expr(&self, left: Expr, right: Expr, env: &Arc<Mutex<Env>>) -> Result<Val> {
         let span = right.span;
         match right.kind {
-            ExprKind::Call { caller, mut args } => {
+            ExprKind::Call {
+                caller,
+                mut args,
+                named,
+            } => {
                 args.insert(0, left);
-                self.eval_call(*caller, args, env, span)
+                self.eval_call(*caller, args, named, env, span)
             }
             ExprKind::Mod { .. } => {
                 let call = ExprKind::Call {
                     caller: Box::new(right),
                     args: vec![],
+                    named: vec![],
                 };
                 self.eval_pipe_expr(left, Expr { kind: call, span }, env)
             }
-            ExprKind::Ident(_) => {
+            ExprKind::Ident(_) | ExprKind::Lambda { .. } => {
                 let args = vec![left];
-                self.eval_call(right, args, env, span)
+                self.eval_call(right, args, vec![], env, span)
             }
             _ => Err(InterpreterError::InvalidPipeCaller { span }.into()),
         }
@@ -292,41 +926,89 @@ impl Interpreter {
         &self,
         caller: Expr,
         args: Vec<Expr>,
+        named: Vec<(Ident, Expr)>,
         env: &Arc<Mutex<Env>>,
         span: SourceSpan,
     ) -> Result<Val> {
         // Since we'll be (potentially) switching environments if the caller is a module,
         // we should parse all of the arguments in the current environment first
-        let args = args
-            .into_iter()
-            .map(|arg| self.eval(arg, env))
-            .collect::<Result<Vec<Val>>>()?;
+        let args = self.eval_items(args, env)?;
+        let named = self.eval_named_args(named, env)?;
 
         // We introduce another parameter here, so we don't lose the original environment
-        self._eval_call(caller, args, env, env, span)
+        self._eval_call(caller, args, named, env, env, span)
+    }
+
+    fn eval_named_args(
+        &self,
+        named: Vec<(Ident, Expr)>,
+        env: &Arc<Mutex<Env>>,
+    ) -> Result<Vec<NamedArg>> {
+        named
+            .into_iter()
+            .map(|(ident, arg)| {
+                let span = arg.span;
+                Ok((ident, self.eval(arg, env)?, span))
+            })
+            .collect()
     }
 
     fn _eval_call(
         &self,
         caller: Expr,
         args: Vec<Val>,
+        named: Vec<NamedArg>,
         env: &Arc<Mutex<Env>>,
         mod_env: &Arc<Mutex<Env>>,
         span: SourceSpan,
     ) -> Result<Val> {
         match caller.kind {
             ExprKind::Mod { module, item } => {
-                // If the caller is a member of a module, find the module
-                let mod_env = self.lookup_module(*module, env)?;
-                // Now call in the module's environment instead
-                return self._eval_call(*item, args, env, &mod_env, span);
+                let module_span = module.span;
+
+                match self.eval(*module, env)?.into_kind() {
+                    // If the caller is a member of a module, call in the module's environment
+                    // instead
+                    ValKind::Mod(mod_env) => {
+                        return self._eval_call(*item, args, named, env, &mod_env, span);
+                    }
+                    // Calling an enum variant constructs it with a payload
+                    ValKind::Enum { ident, variants } => {
+                        let (variant, fields) = find_variant(&ident, variants, &item)?;
+                        let payload = match_fields(&fields, args, named, span)?;
+
+                        return Ok(ValKind::Variant {
+                            ty: ident,
+                            variant,
+                            payload,
+                        }
+                        .into());
+                    }
+                    _ => return Err(InterpreterError::InvalidModule { span: module_span }.into()),
+                }
             }
             ExprKind::Ident(ref ident) => {
                 // Check if the caller is a built-in function
                 if let Some(builtin) = Env::lookup_builtin(mod_env, ident) {
+                    if let Some((_, _, span)) = named.first() {
+                        return Err(InterpreterError::NamedBuiltinArgs { span: *span }.into());
+                    }
+
                     // Run the built-in the original environment
-                    let result = builtin.run(&args, env);
-                    return Ok(result.unwrap_or(Val::NONE));
+                    return match builtin.run(&args, env).map(Val::into_kind) {
+                        // Built-ins raise errors by returning them
+                        Some(ValKind::Error { message, code, .. }) => Err(Exception::Throw {
+                            value: ValKind::Error {
+                                message,
+                                code,
+                                span: Some(span),
+                            }
+                            .into(),
+                            span,
+                        }
+                        .into()),
+                        result => Ok(result.map_or(Val::NONE, Val::from)),
+                    };
                 }
             }
             _ => (),
@@ -337,78 +1019,309 @@ impl Interpreter {
         //
         // Evaluating the call in the module's environment is important, we don't have any
         // of its context
-        let ValKind::Func {
-            params, body, env, ..
-        } = self.eval(caller, mod_env)?.kind
-        else {
-            return Err(InterpreterError::CallerNotDefined { span: caller_span }.into());
+        let func = match self.eval(caller, mod_env)?.into_kind() {
+            ValKind::Func {
+                ident,
+                params,
+                body,
+                env,
+            } => Callee {
+                ident,
+                params,
+                body,
+                env,
+            },
+            // Calling a struct constructs an instance of it
+            ValKind::Struct { ident, fields } => {
+                let values = match_fields(&fields, args, named, span)?;
+                let fields = fields.into_iter().zip(values).collect();
+                return Ok(ValKind::Instance { ident, fields }.into());
+            }
+            _ => return Err(InterpreterError::CallerNotDefined { span: caller_span }.into()),
         };
 
-        if args.len() != params.len() {
-            return Err(InterpreterError::MismatchedArgs { span: caller_span }.into());
-        }
-
-        for (param, arg) in params.into_iter().zip(args.into_iter()) {
-            env.lock().unwrap().declare_unchecked(&param, arg);
-        }
-
-        let mut last = None;
+        let name = func.ident.as_deref().unwrap_or("<anonymous>");
 
-        for stmt in body {
-            // Run the defined function in the original environment
-            let result = self.eval(stmt, &env);
+        if self.frames.borrow().len() >= self.max_depth {
+            let mut chain = self.frames.borrow().clone();
+            chain.push(name.to_string());
 
-            match result {
-                Ok(result) => last = Some(result),
-                Err(kind) => match kind.downcast_ref() {
-                    Some(Exception::Return(value)) => {
-                        last = match value {
-                            Some(value) => Some(self.eval(*value.clone(), &env)?),
-                            None => None,
-                        };
-                        break;
-                    }
-                    _ => return Err(kind),
-                },
+            return Err(InterpreterError::StackOverflow {
+                depth: self.max_depth,
+                chain: format_chain(&chain),
+                span,
             }
+            .into());
         }
 
-        Ok(last.unwrap_or(Val::NONE))
-    }
-
-    fn eval_list_expr(&self, items: Vec<Expr>, env: &Arc<Mutex<Env>>) -> Result<Val> {
-        let items = items
-            .into_iter()
-            .map(|item| self.eval(item, env))
-            .collect::<Result<Vec<Val>>>()?;
+        self.frames.borrow_mut().push(name.to_string());
+        let result = self.eval_func_call(&func, args, named, span);
+        self.frames.borrow_mut().pop();
 
-        Ok(ValKind::List(items).into())
+        result
     }
 
-    fn eval_logic_expr(
+    /// Calls the user-defined function `func`. Self-recursive calls in tail position reuse the
+    /// current call, rather than nesting another.
+    fn eval_func_call(
         &self,
-        left: Expr,
-        right: Expr,
-        op: LogicOp,
+        func: &Callee,
+        mut args: Vec<Val>,
+        mut named: Vec<NamedArg>,
         span: SourceSpan,
-        env: &Arc<Mutex<Env>>,
     ) -> Result<Val> {
-        let lhs = self.eval(left.clone(), env)?.kind;
-        let rhs = self.eval(right.clone(), env)?.kind;
+        loop {
+            // Every call gets a fresh frame, so that recursive calls don't share parameters and
+            // locals
+            let env = Env::with_parent(&func.env);
 
-        let (ValKind::Bool(lhs), ValKind::Bool(rhs)) = (lhs, rhs) else {
-            return Err(InterpreterError::LogicalExpressionUnsupported {
-                span,
-                left: left.kind,
-                right: right.kind,
-                op,
-            }
-            .into());
+            self.bind_params(&func.params, args, named, span, &env)?;
+
+            let result = match self.eval_body_tail(func.body.clone(), func, &env) {
+                Err(report) => match report.downcast::<Exception>() {
+                    Ok(Exception::Return(value)) => Tail::Value(value),
+                    // Loops outside of the function can't be jumped out of
+                    Ok(Exception::Break { span, .. }) => {
+                        return Err(InterpreterError::JumpOutOfFunction {
+                            keyword: "break",
+                            span,
+                        }
+                        .into())
+                    }
+                    Ok(Exception::Continue { span, .. }) => {
+                        return Err(InterpreterError::JumpOutOfFunction {
+                            keyword: "continue",
+                            span,
+                        }
+                        .into())
+                    }
+                    Ok(exception) => return Err(exception.into()),
+                    Err(report) => return Err(report),
+                },
+                result => result?,
+            };
+
+            match result {
+                Tail::Value(value) => return Ok(value),
+                Tail::Call(next_args, next_named) => (args, named) = (next_args, next_named),
+            }
+        }
+    }
+
+    /// Binds the arguments of a call to the parameters `params` in `env`.
+    fn bind_params(
+        &self,
+        params: &[Param],
+        mut args: Vec<Val>,
+        named: Vec<NamedArg>,
+        span: SourceSpan,
+        env: &Arc<Mutex<Env>>,
+    ) -> Result<()> {
+        let expected = || join_params(params);
+
+        // A rest parameter collects any positional arguments left over
+        let (fixed, rest) = match params.split_last() {
+            Some((last, fixed)) if matches!(last.pattern.kind, PatternKind::Rest(_)) => {
+                (fixed, Some(last))
+            }
+            _ => (params, None),
+        };
+
+        let extra = match rest {
+            Some(_) if args.len() > fixed.len() => args.split_off(fixed.len()),
+            _ => vec![],
+        };
+
+        let names = fixed
+            .iter()
+            .map(|param| match &param.pattern.kind {
+                PatternKind::Binding(ident) => Some(ident.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let args = match_args(&names, args, named, span, expected)?;
+
+        for (param, arg) in fixed.iter().zip(args) {
+            // Parameters without an argument fall back to their default, which can refer to
+            // earlier parameters
+            let arg = match (arg, &param.default) {
+                (Some(arg), _) => arg,
+                (None, Some(default)) => self.eval(default.clone(), env)?,
+                (None, None) => {
+                    return Err(InterpreterError::MismatchedArgs {
+                        span,
+                        expected: expected(),
+                    }
+                    .into())
+                }
+            };
+
+            for (ident, value, _) in self.destructure(&param.pattern, arg, span, env)? {
+                env.lock().unwrap().declare_unchecked(&ident, value);
+            }
+        }
+
+        if let Some(PatternKind::Rest(Some(ident))) = rest.map(|param| &param.pattern.kind) {
+            env.lock()
+                .unwrap()
+                .declare_unchecked(ident, ValKind::List(extra).into());
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates `body` like [`Self::eval_body`], with its last statement in tail position.
+    fn eval_body_tail(
+        &self,
+        mut body: Vec<Stmt>,
+        func: &Callee,
+        env: &Arc<Mutex<Env>>,
+    ) -> Result<Tail> {
+        let Some(last) = body.pop() else {
+            return Ok(Tail::Value(Val::NONE));
         };
 
-        let result = match op {
-            LogicOp::And => lhs && rhs,
-            LogicOp::Or => lhs || rhs,
+        self.eval_body(body, env)?;
+
+        match last {
+            Stmt::Expr(expr) => self.eval_tail(expr, func, env),
+            stmt => Ok(Tail::Value(self.eval(stmt, env)?)),
+        }
+    }
+
+    /// Evaluates `expr` in tail position of `func`, where calls to `func` itself are returned
+    /// rather than evaluated.
+    fn eval_tail(&self, expr: Expr, func: &Callee, env: &Arc<Mutex<Env>>) -> Result<Tail> {
+        // Tail positions nest without passing through `Self::eval`, so the stack is guarded here
+        // as well
+        stack::guard(|| match expr.kind {
+            ExprKind::Call {
+                caller,
+                args,
+                named,
+            } if self.is_self_call(&caller, func, env) => Ok(Tail::Call(
+                self.eval_items(args, env)?,
+                self.eval_named_args(named, env)?,
+            )),
+            ExprKind::Cond(cond) => {
+                let Cond {
+                    condition,
+                    body,
+                    alternate,
+                    ..
+                } = *cond;
+
+                if self.eval_condition(condition, env)? {
+                    return self.eval_body_tail(body, func, &Env::with_parent(env));
+                }
+
+                match alternate.map(|alternate| *alternate) {
+                    Some(Alternate::Cond(cond)) => {
+                        let span = cond.span;
+                        let kind = ExprKind::Cond(Box::new(cond));
+                        self.eval_tail(Expr { kind, span }, func, env)
+                    }
+                    Some(Alternate::Body { body, .. }) => {
+                        self.eval_body_tail(body, func, &Env::with_parent(env))
+                    }
+                    None => Ok(Tail::Value(Val::NONE)),
+                }
+            }
+            ExprKind::Block { body } => self.eval_body_tail(body, func, &Env::with_parent(env)),
+            ExprKind::Return { value: Some(value) } => self.eval_tail(*value, func, env),
+            kind => Ok(Tail::Value(self.eval(Expr { kind, ..expr }, env)?)),
+        })
+    }
+
+    /// Checks whether `caller` refers to the named function `func` itself.
+    fn is_self_call(&self, caller: &Expr, func: &Callee, env: &Arc<Mutex<Env>>) -> bool {
+        let (ExprKind::Ident(ident), Some(name)) = (&caller.kind, &func.ident) else {
+            return false;
+        };
+
+        if ident != name {
+            return false;
+        }
+
+        // Functions are identified by their name and the environment they are declared in
+        matches!(
+            Env::lookup(env, ident, caller.span).map(Val::into_kind),
+            Ok(ValKind::Func { ident: Some(ref other), env: ref other_env, .. })
+                if other == name && Arc::ptr_eq(other_env, &func.env)
+        )
+    }
+
+    fn eval_list_expr(&self, items: Vec<Expr>, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        Ok(ValKind::List(self.eval_items(items, env)?).into())
+    }
+
+    /// Evaluates the items of a list or the arguments of a call, expanding any spread items.
+    fn eval_items(&self, items: Vec<Expr>, env: &Arc<Mutex<Env>>) -> Result<Vec<Val>> {
+        let mut values = Vec::with_capacity(items.len());
+
+        for item in items {
+            match item.kind {
+                ExprKind::Spread { expr } => {
+                    let span = expr.span;
+                    values.extend(iterate(self.eval(*expr, env)?, span)?);
+                }
+                _ => values.push(self.eval(item, env)?),
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn eval_map_expr(&self, entries: Vec<(Expr, Expr)>, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        let mut map = BTreeMap::new();
+
+        for (key, value) in entries {
+            let span = key.span;
+
+            let ValKind::Str(key) = self.eval(key, env)?.into_kind() else {
+                return Err(InterpreterError::InvalidMapKey { span }.into());
+            };
+
+            map.insert(key, self.eval(value, env)?);
+        }
+
+        Ok(ValKind::Map(map).into())
+    }
+
+    fn eval_interp_expr(&self, parts: Vec<Expr>, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        let value = parts
+            .into_iter()
+            .map(|part| Ok(self.eval(part, env)?.to_string()))
+            .collect::<Result<String>>()?;
+
+        Ok(ValKind::Str(value).into())
+    }
+
+    fn eval_logic_expr(
+        &self,
+        left: Expr,
+        right: Expr,
+        op: LogicOp,
+        env: &Arc<Mutex<Env>>,
+    ) -> Result<Val> {
+        let operand = |expr: Expr| -> Result<bool> {
+            let span = expr.span;
+
+            match self.eval(expr, env)? {
+                Val {
+                    kind: ValKind::Bool(value),
+                    ..
+                } => Ok(value),
+                value => Err(InterpreterError::NonBoolOperand { span, value, op }.into()),
+            }
+        };
+
+        // The right operand is only evaluated if the left doesn't decide the result
+        let result = match (op, operand(left)?) {
+            (LogicOp::And, false) => false,
+            (LogicOp::Or, true) => true,
+            _ => operand(right)?,
         };
 
         Ok(ValKind::Bool(result).into())
@@ -422,8 +1335,8 @@ impl Interpreter {
         span: SourceSpan,
         env: &Arc<Mutex<Env>>,
     ) -> Result<Val> {
-        let lhs = self.eval(left.clone(), env)?.kind;
-        let rhs = self.eval(right.clone(), env)?.kind;
+        let lhs = self.eval(left.clone(), env)?.into_kind();
+        let rhs = self.eval(right.clone(), env)?.into_kind();
 
         let err = InterpreterError::RelationalExpressionUnsupported {
             span,
@@ -446,17 +1359,68 @@ impl Interpreter {
                 RelOp::Less => lhs < rhs,
                 RelOp::LessEq => lhs <= rhs,
             },
+            // Float comparisons, where integers are promoted to floats
+            (ValKind::Float(_) | ValKind::Int(_), ValKind::Float(_) | ValKind::Int(_)) => {
+                let (lhs, rhs) = (lhs.as_float(), rhs.as_float());
+                match op {
+                    RelOp::Eq => lhs == rhs,
+                    RelOp::NotEq => lhs != rhs,
+                    RelOp::Greater => lhs > rhs,
+                    RelOp::GreaterEq => lhs >= rhs,
+                    RelOp::Less => lhs < rhs,
+                    RelOp::LessEq => lhs <= rhs,
+                }
+            }
             (ValKind::Str(lhs), ValKind::Str(rhs)) => match op {
                 RelOp::Eq => lhs == rhs,
                 RelOp::NotEq => lhs != rhs,
                 _ => return Err(err.into()),
             },
+            // Compound values can only be compared for equality
+            (ValKind::List(_), ValKind::List(_))
+            | (ValKind::Map(_), ValKind::Map(_))
+            | (ValKind::Range(_), ValKind::Range(_))
+            | (ValKind::Instance { .. }, ValKind::Instance { .. })
+            | (ValKind::Variant { .. }, ValKind::Variant { .. }) => match op {
+                RelOp::Eq => values_equal(&lhs, &rhs),
+                RelOp::NotEq => !values_equal(&lhs, &rhs),
+                _ => return Err(err.into()),
+            },
             _ => return Err(err.into()),
         };
 
         Ok(ValKind::Bool(result).into())
     }
 
+    fn eval_range_expr(
+        &self,
+        start: Expr,
+        end: Expr,
+        inclusive: bool,
+        env: &Arc<Mutex<Env>>,
+    ) -> Result<Val> {
+        let eval_bound = |bound: Expr| -> Result<i32> {
+            let span = bound.span;
+            match self.eval(bound, env)?.kind {
+                ValKind::Int(int) => Ok(int),
+                _ => Err(InterpreterError::InvalidRangeBound { span }.into()),
+            }
+        };
+
+        let span = end.span;
+        let start = eval_bound(start)?;
+        let mut end = eval_bound(end)?;
+
+        // Ranges are stored exclusively, so an inclusive end must be shifted by one
+        if inclusive {
+            end = end
+                .checked_add(1)
+                .ok_or(InterpreterError::InvalidRangeBound { span })?;
+        }
+
+        Ok(ValKind::Range(start..end).into())
+    }
+
     fn eval_unary_expr(
         &self,
         expr: Expr,
@@ -475,7 +1439,15 @@ impl Interpreter {
         match result.kind {
             ValKind::Int(value) => match op {
                 UnaryOp::Pos => Ok(result),
-                UnaryOp::Neg => Ok(ValKind::Int(-value).into()),
+                UnaryOp::Neg => match value.checked_neg() {
+                    Some(value) => Ok(ValKind::Int(value).into()),
+                    None => Err(InterpreterError::IntegerOverflow { span }.into()),
+                },
+                _ => Err(err.into()),
+            },
+            ValKind::Float(value) => match op {
+                UnaryOp::Pos => Ok(result),
+                UnaryOp::Neg => Ok(ValKind::Float(-value).into()),
                 _ => Err(err.into()),
             },
             ValKind::Bool(value) => match op {
@@ -494,70 +1466,347 @@ impl Interpreter {
         span: SourceSpan,
         env: &Arc<Mutex<Env>>,
     ) -> Result<Val> {
-        let lhs = self.eval(left.clone(), env)?.kind;
-        let rhs = self.eval(right.clone(), env)?.kind;
+        let lhs = self.eval(left.clone(), env)?.into_kind();
+        let rhs = self.eval(right.clone(), env)?.into_kind();
 
-        let err = InterpreterError::BinaryExpressionUnsupported {
-            span,
-            left: left.kind,
-            right: right.kind,
-            op,
-        };
+        binary_op(lhs, rhs, op, &left, &right, span)
+    }
 
-        let result: ValKind = match (lhs, rhs) {
-            // Integer operations
-            (ValKind::Int(lhs), ValKind::Int(rhs)) => {
-                let value = match op {
-                    BinaryOp::Add => lhs + rhs,
-                    BinaryOp::Sub => lhs - rhs,
-                    BinaryOp::Mul => lhs * rhs,
-                    BinaryOp::Div => lhs / rhs,
-                };
-                ValKind::Int(value)
-            }
-            // String addition.
-            //
-            // Example: "foo" + "bar" -> "foobar"
-            (ValKind::Str(lhs), ValKind::Str(rhs)) => {
-                if op == BinaryOp::Add {
-                    ValKind::Str(format!("{lhs}{rhs}"))
-                } else {
-                    return Err(err.into());
+    fn eval_ident(&self, ident: &Ident, env: &Arc<Mutex<Env>>, span: SourceSpan) -> Result<Val> {
+        let val = Env::lookup(env, ident, span)?;
+        Ok(val)
+    }
+
+    fn eval_index_expr(&self, expr: Expr, index: Expr, env: &Arc<Mutex<Env>>) -> Result<Val> {
+        let (span, index_span) = (expr.span, index.span);
+
+        let container = self.eval(expr, env)?.into_kind();
+        let index = self.eval(index, env)?.into_kind();
+
+        index_value(container, index, span, index_span)
+    }
+
+    fn eval_match_expr(
+        &self,
+        scrutinee: Expr,
+        arms: Vec<MatchArm>,
+        env: &Arc<Mutex<Env>>,
+    ) -> Result<Val> {
+        let span = scrutinee.span;
+        let value = self.eval(scrutinee, env)?;
+
+        for MatchArm {
+            pattern,
+            guard,
+            body,
+        } in arms
+        {
+            // Each arm gets its own scope for the bindings in its pattern
+            let env = Env::with_parent(env);
+
+            let mut bindings = Vec::new();
+            if !self.match_pattern(&pattern, &value, &env, &mut bindings)? {
+                continue;
+            }
+
+            for (ident, value, _) in bindings {
+                env.lock().unwrap().declare_unchecked(&ident, value);
+            }
+
+            if let Some(guard) = guard {
+                if !self.eval_condition(guard, &env)? {
+                    continue;
                 }
             }
-            // String repeating. Integers less than one are not valid.
-            //
-            // Example: "foo" * 2 -> "foofoo".
-            (ValKind::Str(lhs), ValKind::Int(rhs)) => {
-                if op == BinaryOp::Mul && rhs >= 0 {
-                    // Since `rhs` is positive, no need to worry about casting
-                    ValKind::Str(lhs.repeat(rhs as usize))
-                } else {
-                    return Err(err.into());
+
+            return self.eval_body(body, &env);
+        }
+
+        Err(InterpreterError::NoMatchingArm { span }.into())
+    }
+
+    /// Matches `value` against `pattern`, returning its bindings.
+    ///
+    /// Returns an error if the value does not match the pattern.
+    fn destructure(
+        &self,
+        pattern: &Pattern,
+        value: Val,
+        value_span: SourceSpan,
+        env: &Arc<Mutex<Env>>,
+    ) -> Result<Vec<Binding>> {
+        let mut bindings = Vec::new();
+
+        if !self.match_pattern(pattern, &value, env, &mut bindings)? {
+            return Err(InterpreterError::PatternMismatch {
+                value: value.to_string(),
+                pattern: pattern.to_string(),
+                span: pattern.span,
+                value_span,
+            }
+            .into());
+        }
+
+        Ok(bindings)
+    }
+
+    /// Checks whether `value` matches `pattern`, collecting any bindings into `bindings`.
+    ///
+    /// Literals within the pattern are evaluated in `env`.
+    fn match_pattern(
+        &self,
+        pattern: &Pattern,
+        value: &Val,
+        env: &Arc<Mutex<Env>>,
+        bindings: &mut Vec<Binding>,
+    ) -> Result<bool> {
+        let matches = match &pattern.kind {
+            PatternKind::Wildcard => true,
+            PatternKind::Binding(ident) => {
+                bindings.push((ident.clone(), value.clone(), pattern.span));
+                true
+            }
+            PatternKind::Literal(literal) => {
+                let literal = self.eval(literal.clone(), env)?;
+                values_equal(&literal.kind, &value.kind)
+            }
+            PatternKind::List(patterns) => {
+                let ValKind::List(items) = &value.kind else {
+                    return Ok(false);
+                };
+
+                let rest = patterns
+                    .iter()
+                    .position(|pattern| matches!(pattern.kind, PatternKind::Rest(_)));
+
+                let Some(rest) = rest else {
+                    return self.match_patterns(patterns, items, env, bindings);
+                };
+
+                // The rest pattern matches whatever is left between the items before and after it
+                let (before, after) = (&patterns[..rest], &patterns[rest + 1..]);
+                if items.len() < before.len() + after.len() {
+                    return Ok(false);
+                }
+
+                let after_start = items.len() - after.len();
+
+                if !self.match_patterns(before, &items[..before.len()], env, bindings)?
+                    || !self.match_patterns(after, &items[after_start..], env, bindings)?
+                {
+                    return Ok(false);
+                }
+
+                let rest_pattern = &patterns[rest];
+                if let PatternKind::Rest(Some(ident)) = &rest_pattern.kind {
+                    let rest = items[before.len()..after_start].to_vec();
+                    bindings.push((ident.clone(), rest.into(), rest_pattern.span));
                 }
+
+                true
             }
-            (ValKind::Int(lhs), ValKind::Str(rhs)) => {
-                if op == BinaryOp::Mul && lhs >= 0 {
-                    // Since `lhs` is positive, no need to worry about casting
-                    ValKind::Str(rhs.repeat(lhs as usize))
-                } else {
-                    return Err(err.into());
+            PatternKind::Variant {
+                path,
+                variant,
+                payload,
+            } => {
+                let (ty, variants) = self.lookup_enum(path, env, pattern.span)?;
+
+                if !variants.iter().any(|(existing, _)| existing == variant) {
+                    return Err(InterpreterError::UnknownVariant {
+                        ident: ty,
+                        variant: variant.clone(),
+                        span: pattern.span,
+                    }
+                    .into());
+                }
+
+                let ValKind::Variant {
+                    ty: value_ty,
+                    variant: value_variant,
+                    payload: values,
+                } = &value.kind
+                else {
+                    return Ok(false);
+                };
+
+                if *value_ty != ty || value_variant != variant {
+                    return Ok(false);
+                }
+
+                match payload {
+                    Some(payload) => self.match_patterns(payload, values, env, bindings)?,
+                    None => true,
                 }
             }
-            _ => return Err(err.into()),
+            PatternKind::Rest(_) => unreachable!("rest patterns should only occur within lists"),
         };
 
-        Ok(result.into())
+        Ok(matches)
     }
 
-    fn eval_ident(&self, ident: &Ident, env: &Arc<Mutex<Env>>, span: SourceSpan) -> Result<Val> {
-        let val = Env::lookup(env, ident, span)?;
-        Ok(val)
+    /// Looks up the enum at `path`, e.g. `shapes.Shape`, returning its identifier and variants.
+    fn lookup_enum(
+        &self,
+        path: &[Ident],
+        env: &Arc<Mutex<Env>>,
+        span: SourceSpan,
+    ) -> Result<(Ident, Vec<Variant>)> {
+        let (last, modules) = path.split_last().expect("path should not be empty");
+
+        // Every identifier but the last refers to a module
+        let mut env = Arc::clone(env);
+        for ident in modules {
+            let ValKind::Mod(mod_env) = Env::lookup(&env, ident, span)?.into_kind() else {
+                return Err(InterpreterError::InvalidModule { span }.into());
+            };
+            env = mod_env;
+        }
+
+        let value = Env::lookup(&env, last, span)?.into_kind();
+
+        match value {
+            ValKind::Enum { ident, variants } => Ok((ident, variants)),
+            _ => Err(InterpreterError::NotAnEnum { span }.into()),
+        }
+    }
+
+    /// Checks whether each of `items` matches the corresponding pattern in `patterns`.
+    fn match_patterns(
+        &self,
+        patterns: &[Pattern],
+        items: &[Val],
+        env: &Arc<Mutex<Env>>,
+        bindings: &mut Vec<Binding>,
+    ) -> Result<bool> {
+        if patterns.len() != items.len() {
+            return Ok(false);
+        }
+
+        for (pattern, item) in patterns.iter().zip(items) {
+            if !self.match_pattern(pattern, item, env, bindings)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn eval_throw(&self, value: Expr, env: &Arc<Mutex<Env>>, span: SourceSpan) -> Result<Val> {
+        let value = match self.eval(value, env)? {
+            value @ Val {
+                kind: ValKind::Error { .. },
+                ..
+            } => value,
+            // Any other value is thrown as the message of a new error
+            value => ValKind::Error {
+                message: value.to_string(),
+                code: None,
+                span: Some(span),
+            }
+            .into(),
+        };
+
+        Err(Exception::Throw { value, span }.into())
+    }
+
+    fn eval_try(
+        &self,
+        body: Vec<Stmt>,
+        ident: Option<Ident>,
+        catch: Vec<Stmt>,
+        env: &Arc<Mutex<Env>>,
+    ) -> Result<Val> {
+        let report = match self.eval_body(body, &Env::with_parent(env)) {
+            Ok(value) => return Ok(value),
+            Err(report) => report,
+        };
+
+        let error = match report.downcast::<Exception>() {
+            Ok(Exception::Throw { value, .. }) => value,
+            // Other control flow must pass through
+            Ok(exception) => return Err(exception.into()),
+            // Any other error is converted into an error value
+            Err(report) => ValKind::Error {
+                message: report.to_string(),
+                code: report.code().map(|code| code.to_string()),
+                span: report
+                    .labels()
+                    .and_then(|mut labels| labels.next())
+                    .map(|label| *label.inner()),
+            }
+            .into(),
+        };
+
+        let env = Env::with_parent(env);
+
+        if let Some(ident) = ident {
+            env.lock().unwrap().declare_unchecked(&ident, error);
+        }
+
+        self.eval_body(catch, &env)
     }
 
     fn eval_mod_expr(&self, module: Expr, item: Expr, env: &Arc<Mutex<Env>>) -> Result<Val> {
-        let mod_env = self.lookup_module(module, env)?;
-        self.eval(item, &mod_env)
+        let span = module.span;
+
+        match self.eval(module, env)?.into_kind() {
+            ValKind::Mod(mod_env) => self.eval(item, &mod_env),
+            // Accessing a field of a struct instance
+            ValKind::Instance { ident, fields } => {
+                let field = field_name(&ident, &item)?;
+
+                match fields.into_iter().find(|(name, _)| *name == field) {
+                    Some((_, value)) => Ok(value),
+                    None => Err(InterpreterError::UnknownField {
+                        ident,
+                        field,
+                        span: item.span,
+                    }
+                    .into()),
+                }
+            }
+            // Accessing a variant of an enum, which must not have a payload
+            ValKind::Enum { ident, variants } => {
+                let (variant, fields) = find_variant(&ident, variants, &item)?;
+
+                if !fields.is_empty() {
+                    return Err(InterpreterError::MissingPayload {
+                        variant,
+                        len: fields.len(),
+                        span: item.span,
+                    }
+                    .into());
+                }
+
+                Ok(ValKind::Variant {
+                    ty: ident,
+                    variant,
+                    payload: vec![],
+                }
+                .into())
+            }
+            // Accessing the details of an error
+            ValKind::Error {
+                message,
+                code,
+                span: error_span,
+            } => match field_name("Error", &item)?.as_str() {
+                "message" => Ok(ValKind::Str(message).into()),
+                "code" => Ok(code.map_or(Val::NONE, |code| ValKind::Str(code).into())),
+                "span" => Ok(error_span.map_or(Val::NONE, |error_span| {
+                    let start = error_span.offset() as i32;
+                    ValKind::Range(start..start + error_span.len() as i32).into()
+                })),
+                field => Err(InterpreterError::UnknownField {
+                    ident: "Error".to_string(),
+                    field: field.to_string(),
+                    span: item.span,
+                }
+                .into()),
+            },
+            _ => Err(InterpreterError::InvalidModule { span }.into()),
+        }
     }
 
     fn eval_use(&self, path: &str, env: &Arc<Mutex<Env>>, span: SourceSpan) -> Result<Val> {
@@ -570,18 +1819,1208 @@ impl Interpreter {
             |_| Ok(Val::NONE),
         )
     }
+}
 
-    fn lookup_module(&self, module: Expr, env: &Arc<Mutex<Env>>) -> Result<Arc<Mutex<Env>>> {
-        let span = module.span;
+/// Returns an iterator over the items of `value`, which must be a list, string or range.
+fn iterate(value: Val, span: SourceSpan) -> Result<Box<dyn Iterator<Item = Val>>> {
+    match value.into_kind() {
+        ValKind::List(items) => Ok(Box::new(items.into_iter())),
+        ValKind::Str(value) => Ok(Box::new(
+            value
+                .chars()
+                .map(|ch| ValKind::Str(ch.to_string()).into())
+                .collect::<Vec<_>>()
+                .into_iter(),
+        )),
+        // Ranges are iterated lazily, rather than materialized into a list
+        ValKind::Range(range) => Ok(Box::new(range.map(|int| ValKind::Int(int).into()))),
+        _ => Err(InterpreterError::NotIterable { span }.into()),
+    }
+}
 
-        let Val {
-            kind: ValKind::Mod(mod_env),
-            ..
-        } = self.eval(module, env)?
-        else {
-            return Err(InterpreterError::InvalidModule { span }.into());
+/// Matches positional and named arguments to the parameters named `names`, in order. Parameters
+/// that cannot be named, e.g. destructuring patterns, have no name.
+///
+/// Returns the argument for each parameter, or `None` if it wasn't passed.
+fn match_args(
+    names: &[Option<&str>],
+    args: Vec<Val>,
+    named: Vec<NamedArg>,
+    span: SourceSpan,
+    expected: impl Fn() -> String,
+) -> Result<Vec<Option<Val>>> {
+    if args.len() > names.len() {
+        return Err(InterpreterError::MismatchedArgs {
+            span,
+            expected: expected(),
+        }
+        .into());
+    }
+
+    let positional = args.len();
+
+    let mut slots = args.into_iter().map(Some).collect::<Vec<_>>();
+    slots.resize(names.len(), None);
+
+    for (ident, value, span) in named {
+        let Some(index) = names.iter().position(|name| *name == Some(ident.as_str())) else {
+            return Err(InterpreterError::UnknownArg {
+                ident,
+                span,
+                expected: expected(),
+            }
+            .into());
         };
 
-        Ok(mod_env)
+        if slots[index].is_some() {
+            let passed = if index < positional {
+                "by position"
+            } else {
+                "by name"
+            };
+            return Err(InterpreterError::DuplicateArg {
+                ident,
+                passed,
+                span,
+            }
+            .into());
+        }
+
+        slots[index] = Some(value);
+    }
+
+    Ok(slots)
+}
+
+/// Matches positional and named arguments to the fields of a struct or enum variant, all of which
+/// must be passed.
+fn match_fields(
+    fields: &[Ident],
+    args: Vec<Val>,
+    named: Vec<NamedArg>,
+    span: SourceSpan,
+) -> Result<Vec<Val>> {
+    let names = fields
+        .iter()
+        .map(|field| Some(field.as_str()))
+        .collect::<Vec<_>>();
+    let expected = || fields.join(", ");
+
+    match_args(&names, args, named, span, expected)?
+        .into_iter()
+        .map(|value| {
+            value.ok_or_else(|| {
+                InterpreterError::MismatchedArgs {
+                    span,
+                    expected: expected(),
+                }
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// Formats a chain of calls for display, collapsing repeated calls, e.g. `main → fib (×999)`.
+///
+/// Long chains only show the calls at either end.
+fn format_chain(chain: &[Ident]) -> String {
+    const SHOWN: usize = 4;
+
+    let mut runs: Vec<(&str, usize)> = Vec::new();
+    for ident in chain {
+        match runs.last_mut() {
+            Some((last, count)) if last == ident => *count += 1,
+            _ => runs.push((ident, 1)),
+        }
+    }
+
+    let format_run = |(ident, count): &(&str, usize)| match count {
+        1 => ident.to_string(),
+        count => format!("{ident} (×{count})"),
+    };
+
+    if runs.len() <= SHOWN * 2 {
+        return runs.iter().map(format_run).collect::<Vec<_>>().join(" → ");
+    }
+
+    let start = runs[..SHOWN].iter().map(format_run).collect::<Vec<_>>();
+    let end = runs[runs.len() - SHOWN..]
+        .iter()
+        .map(format_run)
+        .collect::<Vec<_>>();
+
+    format!("{} → … → {}", start.join(" → "), end.join(" → "))
+}
+
+/// Joins the parameters of a function for display, e.g. `a, [b, c], verbose = …`.
+fn join_params(params: &[Param]) -> String {
+    params
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the name of the field of struct `ident` accessed by `item`, which must be an identifier.
+fn field_name(ident: &str, item: &Expr) -> Result<Ident> {
+    match &item.kind {
+        ExprKind::Ident(field) => Ok(field.clone()),
+        _ => Err(InterpreterError::UnknownField {
+            ident: ident.to_string(),
+            field: item.kind.to_string(),
+            span: item.span,
+        }
+        .into()),
+    }
+}
+
+/// Finds the variant of enum `ident` accessed by `item`, returning its identifier and payload
+/// fields.
+fn find_variant(ident: &str, variants: Vec<Variant>, item: &Expr) -> Result<Variant> {
+    let variant = match &item.kind {
+        ExprKind::Ident(variant) => variant.clone(),
+        kind => kind.to_string(),
+    };
+
+    variants
+        .into_iter()
+        .find(|(existing, _)| *existing == variant)
+        .ok_or_else(|| {
+            InterpreterError::UnknownVariant {
+                ident: ident.to_string(),
+                variant,
+                span: item.span,
+            }
+            .into()
+        })
+}
+
+/// Checks whether two values are equal, where integers and floats are compared numerically.
+fn values_equal(lhs: &ValKind, rhs: &ValKind) -> bool {
+    stack::guard(|| match (lhs, rhs) {
+        (ValKind::None, ValKind::None) => true,
+        (ValKind::Bool(lhs), ValKind::Bool(rhs)) => lhs == rhs,
+        (ValKind::Int(lhs), ValKind::Int(rhs)) => lhs == rhs,
+        (ValKind::Float(_) | ValKind::Int(_), ValKind::Float(_) | ValKind::Int(_)) => {
+            lhs.as_float() == rhs.as_float()
+        }
+        (ValKind::Str(lhs), ValKind::Str(rhs)) => lhs == rhs,
+        (ValKind::Range(lhs), ValKind::Range(rhs)) => lhs == rhs,
+        (ValKind::List(lhs), ValKind::List(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs
+                    .iter()
+                    .zip(rhs)
+                    .all(|(lhs, rhs)| values_equal(&lhs.kind, &rhs.kind))
+        }
+        (
+            ValKind::Instance {
+                ident: lhs_ident,
+                fields: lhs,
+            },
+            ValKind::Instance {
+                ident: rhs_ident,
+                fields: rhs,
+            },
+        ) => {
+            lhs_ident == rhs_ident
+                && lhs.len() == rhs.len()
+                && lhs.iter().zip(rhs).all(|((lfield, lhs), (rfield, rhs))| {
+                    lfield == rfield && values_equal(&lhs.kind, &rhs.kind)
+                })
+        }
+        (
+            ValKind::Variant {
+                ty: lhs_ty,
+                variant: lhs_variant,
+                payload: lhs,
+            },
+            ValKind::Variant {
+                ty: rhs_ty,
+                variant: rhs_variant,
+                payload: rhs,
+            },
+        ) => {
+            lhs_ty == rhs_ty
+                && lhs_variant == rhs_variant
+                && lhs.len() == rhs.len()
+                && lhs
+                    .iter()
+                    .zip(rhs)
+                    .all(|(lhs, rhs)| values_equal(&lhs.kind, &rhs.kind))
+        }
+        (ValKind::Map(lhs), ValKind::Map(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs.iter().zip(rhs).all(|((lkey, lhs), (rkey, rhs))| {
+                    lkey == rkey && values_equal(&lhs.kind, &rhs.kind)
+                })
+        }
+        _ => false,
+    })
+}
+
+/// Applies the binary operation `op` to `lhs` and `rhs`, the values of the expressions `left` and
+/// `right`.
+fn binary_op(
+    lhs: ValKind,
+    rhs: ValKind,
+    op: BinaryOp,
+    left: &Expr,
+    right: &Expr,
+    span: SourceSpan,
+) -> Result<Val> {
+    let err = || InterpreterError::BinaryExpressionUnsupported {
+        span,
+        left: left.kind.clone(),
+        right: right.kind.clone(),
+        op,
+    };
+
+    let result: ValKind = match (lhs, rhs) {
+        // Integer operations
+        (ValKind::Int(lhs), ValKind::Int(rhs)) => {
+            let value = match op {
+                BinaryOp::Div | BinaryOp::Rem if rhs == 0 => {
+                    return Err(InterpreterError::DivisionByZero { span: right.span }.into())
+                }
+                BinaryOp::Shl | BinaryOp::Shr if !(0..32).contains(&rhs) => {
+                    return Err(InterpreterError::InvalidShift {
+                        span: right.span,
+                        amount: rhs,
+                    }
+                    .into())
+                }
+                // Negative exponents produce fractions, so fall back to floats
+                BinaryOp::Pow if rhs < 0 => {
+                    return Ok(ValKind::Float(f64::from(lhs).powi(rhs)).into())
+                }
+                BinaryOp::Add => lhs.checked_add(rhs),
+                BinaryOp::Sub => lhs.checked_sub(rhs),
+                BinaryOp::Mul => lhs.checked_mul(rhs),
+                BinaryOp::Div => lhs.checked_div(rhs),
+                BinaryOp::Rem => lhs.checked_rem(rhs),
+                // Since `rhs` is positive, no need to worry about casting
+                BinaryOp::Pow => lhs.checked_pow(rhs as u32),
+                BinaryOp::BitAnd => Some(lhs & rhs),
+                BinaryOp::BitOr => Some(lhs | rhs),
+                BinaryOp::BitXor => Some(lhs ^ rhs),
+                BinaryOp::Shl => Some(lhs << rhs),
+                BinaryOp::Shr => Some(lhs >> rhs),
+            };
+            let Some(value) = value else {
+                return Err(InterpreterError::IntegerOverflow { span }.into());
+            };
+            ValKind::Int(value)
+        }
+        // Float operations, where integers are promoted to floats
+        (
+            lhs @ (ValKind::Float(_) | ValKind::Int(_)),
+            rhs @ (ValKind::Float(_) | ValKind::Int(_)),
+        ) => {
+            let (lhs, rhs) = (lhs.as_float(), rhs.as_float());
+            let value = match op {
+                BinaryOp::Add => lhs + rhs,
+                BinaryOp::Sub => lhs - rhs,
+                BinaryOp::Mul => lhs * rhs,
+                BinaryOp::Div => lhs / rhs,
+                BinaryOp::Rem => lhs % rhs,
+                BinaryOp::Pow => lhs.powf(rhs),
+                // Bitwise operations are only supported for integers
+                _ => return Err(err().into()),
+            };
+            ValKind::Float(value)
+        }
+        // String addition.
+        //
+        // Example: "foo" + "bar" -> "foobar"
+        (ValKind::Str(lhs), ValKind::Str(rhs)) if op == BinaryOp::Add => {
+            ValKind::Str(format!("{lhs}{rhs}"))
+        }
+        // String repeating. Integers less than one are not valid.
+        //
+        // Example: "foo" * 2 -> "foofoo".
+        (ValKind::Str(lhs), ValKind::Int(rhs)) if op == BinaryOp::Mul && rhs >= 0 => {
+            // Since `rhs` is positive, no need to worry about casting
+            ValKind::Str(lhs.repeat(rhs as usize))
+        }
+        (ValKind::Int(lhs), ValKind::Str(rhs)) if op == BinaryOp::Mul && lhs >= 0 => {
+            // Since `lhs` is positive, no need to worry about casting
+            ValKind::Str(rhs.repeat(lhs as usize))
+        }
+        _ => return Err(err().into()),
+    };
+
+    Ok(result.into())
+}
+
+/// Indexes `container` by `index`, where `span` and `index_span` are the spans of their
+/// expressions.
+fn index_value(
+    container: ValKind,
+    index: ValKind,
+    span: SourceSpan,
+    index_span: SourceSpan,
+) -> Result<Val> {
+    let out_of_bounds = |index: i32, len: usize| InterpreterError::IndexOutOfBounds {
+        span: index_span,
+        index,
+        len,
+    };
+    let slice_out_of_bounds = |range: &Range<i32>, len: usize| InterpreterError::SliceOutOfBounds {
+        span: index_span,
+        start: range.start,
+        end: range.end,
+        len,
+    };
+
+    let result = match (container, index) {
+        (ValKind::List(items), ValKind::Int(index)) => wrap_index(index, items.len())
+            .map(|idx| items[idx].clone())
+            .ok_or_else(|| out_of_bounds(index, items.len()))?,
+        (ValKind::List(items), ValKind::Range(range)) => slice_bounds(&range, items.len())
+            .map(|bounds| items[bounds].to_vec().into())
+            .ok_or_else(|| slice_out_of_bounds(&range, items.len()))?,
+        // Strings are indexed by character rather than byte
+        (ValKind::Str(value), ValKind::Int(index)) => {
+            let chars = value.chars().collect::<Vec<_>>();
+            wrap_index(index, chars.len())
+                .map(|idx| ValKind::Str(chars[idx].to_string()).into())
+                .ok_or_else(|| out_of_bounds(index, chars.len()))?
+        }
+        (ValKind::Str(value), ValKind::Range(range)) => {
+            let chars = value.chars().collect::<Vec<_>>();
+            slice_bounds(&range, chars.len())
+                .map(|bounds| ValKind::Str(chars[bounds].iter().collect()).into())
+                .ok_or_else(|| slice_out_of_bounds(&range, chars.len()))?
+        }
+        (ValKind::Map(entries), ValKind::Str(key)) => match entries.get(&key) {
+            Some(value) => value.clone(),
+            None => {
+                return Err(InterpreterError::KeyNotFound {
+                    span: index_span,
+                    key,
+                }
+                .into())
+            }
+        },
+        (ValKind::List(_) | ValKind::Str(_) | ValKind::Map(_), _) => {
+            return Err(InterpreterError::InvalidIndex { span: index_span }.into())
+        }
+        _ => return Err(InterpreterError::NotIndexable { span }.into()),
+    };
+
+    Ok(result)
+}
+
+/// Converts a possibly negative index into an index into a collection of length `len`, where
+/// negative indexes count backwards from the end, e.g. `-1` is the last item.
+///
+/// Returns `None` if the index is out of bounds.
+fn wrap_index(index: i32, len: usize) -> Option<usize> {
+    let len = i32::try_from(len).ok()?;
+    let index = if index < 0 { len + index } else { index };
+
+    usize::try_from(index)
+        .ok()
+        .filter(|&index| index < len as usize)
+}
+
+/// Converts a range into bounds for slicing a collection of length `len`.
+///
+/// Returns `None` if the range is out of bounds.
+fn slice_bounds(range: &Range<i32>, len: usize) -> Option<Range<usize>> {
+    let start = usize::try_from(range.start).ok()?;
+    let end = usize::try_from(range.end).ok()?;
+
+    (start <= end && end <= len).then_some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{Parser, ParserError};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct NoUseHook;
+
+    impl UseHook for NoUseHook {
+        fn eval_use(&self, _: &Interpreter, _: String, _: &Arc<Mutex<Env>>) -> Result<Option<()>> {
+            Ok(None)
+        }
+    }
+
+    #[derive(Default)]
+    struct NoModuleHook;
+
+    impl ModuleHook for NoModuleHook {
+        fn use_module(&self, _: String, _: &Arc<Mutex<Env>>) -> Option<()> {
+            None
+        }
+    }
+
+    /// Evaluates `source`, returning the value of its last statement.
+    fn eval(source: &str) -> Result<Val> {
+        eval_with_depth(source, DEFAULT_MAX_DEPTH)
+    }
+
+    /// Evaluates `source` with a maximum call depth of `max_depth`.
+    fn eval_with_depth(source: &str, max_depth: usize) -> Result<Val> {
+        let ast = Parser::new(source).produce_ast()?;
+        Interpreter::new::<NoUseHook, NoModuleHook>()
+            .with_max_depth(max_depth)
+            .eval(ast, &Env::new())
+    }
+
+    #[test]
+    fn else_branches() {
+        let src = "
+            fn sign(n) {
+                if n > 0 { 1 } else if n < 0 { -1 } else { 0 }
+            }
+            [sign(5), sign(-5), sign(0)]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[1, -1, 0]",
+            "The first branch whose condition holds should be taken"
+        );
+    }
+
+    #[test]
+    fn float_arithmetic() {
+        let src = "
+            [1 + 0.5, 3 / 2, 3.0 / 2, 0.5 < 1]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[1.5, 1, 1.5, true]",
+            "Mixing integers and floats should produce floats"
+        );
+    }
+
+    #[test]
+    fn const_reassignment() {
+        let src = "
+            const limit = 1
+            limit = 2
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert_eq!(error.to_string(), "cannot assign twice to constant");
+    }
+
+    #[test]
+    fn map_literals() {
+        let src = "
+            [#{\"b\": 2, \"a\": 1}, #{}]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[#{a: 1, b: 2}, #{}]",
+            "Maps should be ordered by key"
+        );
+    }
+
+    #[test]
+    fn invalid_map_key() {
+        let src = "
+            #{\"a\": 1, 2: 3}
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::InvalidMapKey { .. })
+        ));
+    }
+
+    #[test]
+    fn lambdas() {
+        let src = "
+            let double = fn(x) { x * 2 }
+            let apply = fn(f, x) { f(x) }
+            [double(2), apply(double, 3), 4 |> fn(x) { x + 1 }]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[4, 6, 5]",
+            "Anonymous functions should be callable like any other value"
+        );
+    }
+
+    #[test]
+    fn while_loop() {
+        let src = "
+            let i = 0
+            let total = 0
+            while i < 5 {
+                i = i + 1
+                if i == 2 { continue }
+                total = total + i
+            }
+            total
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "13",
+            "The loop should run while its condition holds"
+        );
+    }
+
+    #[test]
+    fn interpolation() {
+        let src = "
+            let count = 2
+            \"total: {count * 2} {{literal}}\"
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "total: 4 {literal}",
+            "Interpolated expressions should be evaluated"
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        let src = r#"
+            ["a\tb\u{41}" == "a	bA", r"\n{x}" == "\\n\{x\}"]
+        "#;
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[true, true]",
+            "Escapes should be evaluated, except in raw strings"
+        );
+    }
+
+    #[test]
+    fn index_and_slice() {
+        let src = "
+            let list = [1, 2, 3]
+            list[-1] = 4
+            let map = #{\"a\": 1}
+            map[\"b\"] = map[\"a\"] + 1
+            [list[0], list[-1], list[0..2], \"foo\"[1], \"foo\"[1..3], map]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[1, 4, [1, 2], o, oo, #{a: 1, b: 2}]",
+            "Negative indices should count from the end"
+        );
+    }
+
+    #[test]
+    fn destructuring() {
+        let src = "
+            let [first, ..rest] = [1, 2, 3]
+            fn head([x, ..]) { x }
+            [first, rest, head([4, 5])]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[1, [2, 3], 4]",
+            "Patterns should bind the matching parts of the value"
+        );
+    }
+
+    #[test]
+    fn structs() {
+        let src = "
+            struct Point { x, y }
+            let point = Point(1, 2)
+            point.y += 1
+            [point.x, point.y, point]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[1, 3, Point { x: 1, y: 3 }]",
+            "Struct fields should be readable and assignable"
+        );
+    }
+
+    #[test]
+    fn unknown_field() {
+        let src = "
+            struct Point { x, y }
+            let point = Point(1, 2)
+            point.z
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::UnknownField { ident, field, .. })
+                if ident == "Point" && field == "z"
+        ));
+    }
+
+    #[test]
+    fn enums() {
+        let src = "
+            enum Shape {
+                Circle(radius)
+                Rect(width, height)
+                Empty
+            }
+            fn area(shape) {
+                match shape {
+                    Shape.Rect(w, h) => w * h,
+                    Shape.Circle(r) => 3 * r * r,
+                    Shape.Empty => 0,
+                }
+            }
+            [area(Shape.Rect(2, 3)), area(Shape.Circle(1)), area(Shape.Empty)]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[6, 3, 0]",
+            "Variants should be matched by their payload"
+        );
+    }
+
+    #[test]
+    fn unknown_variant() {
+        let src = "
+            enum Shape { Circle(radius) }
+            Shape.Square(1)
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::UnknownVariant { ident, variant, .. })
+                if ident == "Shape" && variant == "Square"
+        ));
+    }
+
+    #[test]
+    fn block_expressions() {
+        let src = "
+            let a = 1
+            let b = {
+                let a = 2
+                a * 3
+            }
+            let c = if a > b { a } else { b }
+            [a, b, c]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[1, 6, 6]",
+            "Blocks should have their own scope and evaluate to their last expression"
+        );
+    }
+
+    #[test]
+    fn block_scope() {
+        let src = "
+            let outer = {
+                let inner = 1
+                inner + 1
+            }
+            inner
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert_eq!(error.to_string(), "identifier not found");
+    }
+
+    #[test]
+    fn labeled_break() {
+        let src = "
+            let pair = 'outer: for i in 0..5 {
+                for j in 0..5 {
+                    if i * j == 6 { break 'outer [i, j] }
+                }
+            }
+            let i = 0
+            let found = loop {
+                i += 1
+                if i == 3 { break i * 2 }
+            }
+            [pair, found]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[[2, 3], 6]",
+            "Breaks should exit the labeled loop with their value"
+        );
+    }
+
+    #[test]
+    fn undeclared_label() {
+        let src = "
+            'outer: loop {
+                loop { break 'inner }
+            }
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<ParserError>(),
+            Some(ParserError::UndeclaredLabel { label, .. }) if label == "inner"
+        ));
+    }
+
+    #[test]
+    fn default_and_named_args() {
+        let src = "
+            fn greet(name, greeting = \"hello\", punctuation = \"!\") {
+                \"{greeting}, {name}{punctuation}\"
+            }
+            [greet(\"dom\"), greet(\"dom\", punctuation = \"?\"), greet(greeting = \"hi\", name = \"dom\")]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[hello, dom!, hello, dom?, hi, dom!]",
+            "Missing arguments should fall back to their defaults"
+        );
+    }
+
+    #[test]
+    fn unknown_and_missing_args() {
+        let src = "
+            fn greet(name, greeting = \"hello\") { greeting }
+            greet(\"dom\", punctuation = \"!\")
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::UnknownArg { ident, .. }) if ident == "punctuation"
+        ));
+
+        let src = "
+            fn greet(name, greeting = \"hello\") { greeting }
+            greet(greeting = \"hi\")
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::MismatchedArgs { .. })
+        ));
+    }
+
+    #[test]
+    fn rest_and_spread() {
+        let src = "
+            fn count(first, ..rest) { [first, rest] }
+            let items = [2, 3]
+            [count(1), count(1, ..items), [0, ..items, ..4..6]]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[[1, []], [1, [2, 3]], [0, 2, 3, 4, 5]]",
+            "Rest parameters should collect any remaining arguments"
+        );
+    }
+
+    #[test]
+    fn spread_non_iterable() {
+        let src = "
+            fn count(..items) { items }
+            count(1, ..2)
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::NotIterable { .. })
+        ));
+    }
+
+    #[test]
+    fn recursion() {
+        let src = "
+            fn fib(n) {
+                if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+            }
+            fib(15)
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "610",
+            "Recursive calls should not share parameters"
+        );
+    }
+
+    #[test]
+    fn mutual_recursion() {
+        let src = "
+            fn is_even(n) {
+                if n == 0 { true } else { is_odd(n - 1) }
+            }
+            fn is_odd(n) {
+                if n == 0 { false } else { is_even(n - 1) }
+            }
+            [is_even(10), is_odd(7), is_even(7)]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[true, true, false]",
+            "Mutually recursive functions should be able to call each other"
+        );
+    }
+
+    #[test]
+    fn closures() {
+        let src = "
+            fn counter() {
+                let count = 0
+                fn() {
+                    count += 1
+                    count
+                }
+            }
+            fn call_nested(f, depth) {
+                if depth == 0 { f() } else { call_nested(f, depth - 1) }
+            }
+            let a = counter()
+            let b = counter()
+            a()
+            call_nested(a, 5)
+            [call_nested(a, 3), b()]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[3, 1]",
+            "Closures should capture their own environment, regardless of where they are called"
+        );
+    }
+
+    #[test]
+    fn stack_overflow() {
+        let src = "
+            fn is_even(n) {
+                if n == 0 { true } else { !is_odd(n - 1) }
+            }
+            fn is_odd(n) {
+                if n == 0 { false } else { !is_even(n - 1) }
+            }
+            is_even(100)
+        ";
+        let error = eval_with_depth(src, 20).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::StackOverflow { depth: 20, chain, .. })
+                if chain == "is_even → is_odd → is_even → is_odd → … → is_odd → is_even → is_odd → is_even"
+        ));
+    }
+
+    #[test]
+    fn tail_call() {
+        let src = "
+            fn sum(n, total = 0) {
+                if n == 0 { return total }
+                sum(n - 1, total = total + n)
+            }
+            sum(1000)
+        ";
+        let result = eval_with_depth(src, 20).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "500500",
+            "Self-recursive tail calls should not nest"
+        );
+    }
+
+    #[test]
+    fn return_from_loop() {
+        let src = "
+            fn find(xs) {
+                for x in xs {
+                    if x > 1 { return x }
+                }
+                0
+            }
+            [find([1, 2, 3]), find([])]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[2, 0]",
+            "Returning from a loop should evaluate the value in the loop's scope"
+        );
+    }
+
+    #[test]
+    fn return_match_binding() {
+        let src = "
+            fn first(xs) {
+                match xs {
+                    [a, ..] => { return a },
+                    _ => 0,
+                }
+            }
+            [first([4, 5]), first([])]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[4, 0]",
+            "Returning from a match arm should be able to use its bindings"
+        );
+    }
+
+    #[test]
+    fn try_return() {
+        let src = "
+            fn divide(a, b) {
+                try { return a / b } catch err { err.code }
+            }
+            [divide(4, 2), divide(1, 0)]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[2, interpreter::division_by_zero]",
+            "Errors raised by a returned expression should be caught"
+        );
+    }
+
+    #[test]
+    fn break_out_of_function() {
+        let src = "
+            let result = loop {
+                let f = fn() { break 42 }
+                f()
+                break 0
+            }
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::JumpOutOfFunction {
+                keyword: "break",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn compound_assignment_index() {
+        let src = "
+            let calls = 0
+            fn next() {
+                calls += 1
+                calls
+            }
+            let xs = [10, 20, 30]
+            xs[next()] += 1
+            [xs, calls]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[[10, 21, 30], 1]",
+            "The index of a compound assignment should only be evaluated once"
+        );
+    }
+
+    #[test]
+    fn short_circuit() {
+        let src = "[false && boom(), true || boom(), true && false, false || true]";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[false, true, false, true]",
+            "The right operand should only be evaluated if needed"
+        );
+    }
+
+    #[test]
+    fn non_bool_operand() {
+        let error = eval("true && 1").expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::NonBoolOperand { span, .. }) if *span == (8, 1).into()
+        ));
+    }
+
+    #[test]
+    fn negation_overflow() {
+        let src = "
+            let min = -2147483647 - 1
+            let max = -min
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::IntegerOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn range_equality() {
+        let src = "[0..3 == 0..3, 0..3 == 0..=3, 0..3 != 1..3]";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[true, false, true]",
+            "Ranges should be compared by their bounds"
+        );
+    }
+
+    #[test]
+    fn duplicate_arg() {
+        let src = "
+            fn greet(name, greeting = \"hello\") { greeting }
+            greet(\"dom\", name = \"dom\")
+        ";
+        let error = eval(src).expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<InterpreterError>(),
+            Some(InterpreterError::DuplicateArg { ident, passed: "by position", .. })
+                if ident == "name"
+        ));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let body = (0..40).fold("n".to_string(), |body, _| {
+            format!("{{ if true {{ {body} }} else {{ 0 }} }}")
+        });
+        let src = format!(
+            "
+            fn f(n) {{
+                if n == 0 {{ return 0 }}
+                let r = f(n - 1)
+                {body}
+            }}
+            f(999)
+            "
+        );
+
+        // Evaluation allocates more stack as it needs it, so even a small stack is enough
+        let result = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || eval(&src).map(|value| value.to_string()))
+            .expect("should be able to spawn thread")
+            .join()
+            .expect("evaluation should not overflow the stack")
+            .expect("should be able to evaluate");
+
+        assert_eq!(result, "999");
+    }
+
+    #[test]
+    fn deeply_nested_value() {
+        let src = "
+            fn nest(n) {
+                if n == 0 { [] } else { [n, nest(n - 1)] }
+            }
+            let list = nest(10000)
+            let copy = list
+            let text = \"{list}\"
+            let equal = list == copy
+            copy = []
+            [equal, text[0..13]]
+        ";
+
+        // Values are cloned, compared, displayed and dropped under the same guard as evaluation
+        let result = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || eval_with_depth(src, 20_000).map(|value| value.to_string()))
+            .expect("should be able to spawn thread")
+            .join()
+            .expect("walking the value should not overflow the stack")
+            .expect("should be able to evaluate");
+
+        assert_eq!(result, "[true, [10000, [9999]");
+    }
+
+    #[test]
+    fn long_chain() {
+        let chain = vec!["1"; 1000].join(" + ");
+        let src = format!(
+            "
+            fn sum(n) {{
+                if n == 0 {{ return {chain} }}
+                sum(n - 1)
+            }}
+            sum(500)
+            "
+        );
+
+        // Chain links don't count towards the nesting depth, and evaluating a long chain allocates
+        // more stack as it needs it
+        let result = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || eval(&src).map(|value| value.to_string()))
+            .expect("should be able to spawn thread")
+            .join()
+            .expect("evaluation should not overflow the stack")
+            .expect("should be able to evaluate");
+
+        assert_eq!(result, "1000");
+    }
+
+    #[test]
+    fn member_chains() {
+        let src = "
+            struct Point { x, y }
+            fn origin() { Point(0, 0) }
+            let points = [Point(1, 2)]
+            let named = #{\"a\": Point(3, 4)}
+            points[0].x = 9
+            named[\"a\"].y += 1
+            [points[0].x, named[\"a\"].y, origin().x]
+        ";
+        let result = eval(src).expect("should be able to evaluate");
+
+        assert_eq!(
+            result.to_string(),
+            "[9, 5, 0]",
+            "Fields should be accessible after calls and indexes"
+        );
     }
 }
@@ -3,6 +3,8 @@ use std::{iter::Peekable, str::Chars};
 use miette::{Diagnostic, Result, SourceSpan};
 use thiserror::Error;
 
+use crate::ast::BinaryOp;
+
 #[derive(Error, Diagnostic, Debug)]
 pub enum LexerError {
     #[error("token `{ch}` is invalid")]
@@ -16,6 +18,43 @@ pub enum LexerError {
         #[label("string beginning here never terminated")]
         span: SourceSpan,
     },
+    #[error("interpolated expression was never closed")]
+    #[diagnostic(help("literal braces can be written as `{{{{` or `\\{{`"))]
+    UnclosedInterpolation {
+        #[label("this `{{` is never closed")]
+        span: SourceSpan,
+    },
+    #[error("escape sequence `{sequence}` is invalid")]
+    #[diagnostic(help(
+        "valid escape sequences are `\\n`, `\\r`, `\\t`, `\\0`, `\\\\`, `\\\"`, `\\{{`, `\\}}` and `\\u{{..}}`"
+    ))]
+    InvalidEscape {
+        sequence: String,
+        #[label("this escape sequence is invalid")]
+        span: SourceSpan,
+    },
+}
+
+/// An error encountered while scanning the contents of a string.
+#[derive(Debug)]
+pub(crate) enum ScanError {
+    /// The string was never terminated.
+    Unterminated,
+    /// An interpolated expression was never closed, where the given index is of its `{`.
+    UnclosedInterpolation(usize),
+    /// An invalid escape sequence was found, at the given index and with the given length.
+    InvalidEscape(usize, usize),
+}
+
+impl ScanError {
+    /// Shifts the index of the error, e.g. when scanning nested strings.
+    fn shift(self, by: usize) -> Self {
+        match self {
+            Self::InvalidEscape(idx, len) => Self::InvalidEscape(idx + by, len),
+            Self::UnclosedInterpolation(idx) => Self::UnclosedInterpolation(idx + by),
+            Self::Unterminated => Self::Unterminated,
+        }
+    }
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
@@ -39,17 +78,32 @@ pub enum TokenKind<'a> {
     // Literals
     Bool(&'a str),
     Ident(&'a str),
+    /// A loop label, e.g. `'outer`, without its leading quote.
+    Label(&'a str),
     Int(&'a str),
+    Float(&'a str),
     Str(&'a str),
+    RawStr(&'a str),
 
     // Keywords
     Let,
+    Const,
     Cond,
+    Else,
     Func,
     Return,
     Loop,
+    While,
+    For,
+    In,
     Continue,
     Break,
+    Match,
+    Struct,
+    Enum,
+    Throw,
+    Try,
+    Catch,
 
     // Operators
     And,
@@ -57,11 +111,21 @@ pub enum TokenKind<'a> {
     Plus,
     Minus,
     Star,
+    DoubleStar,
     Slash,
+    Percent,
     Bang,
+    Ampersand,
+    Bar,
+    Caret,
+    LeftShift,
+    RightShift,
     RelOp(RelOp),
     Assignment,
+    CompoundAssignment(BinaryOp),
     Separator,
+    Colon,
+    FatArrow,
 
     // Grouping
     LeftParen,
@@ -70,11 +134,16 @@ pub enum TokenKind<'a> {
     RightBrace,
     LeftBracket,
     RightBracket,
+    LeftMapBrace,
 
     // Modules
     Use,
     Dot,
 
+    // Ranges
+    Range,
+    RangeInclusive,
+
     // Misc
     Pipe,
     EndOfLine,
@@ -86,6 +155,8 @@ pub struct Lexer<'a> {
     chars: Peekable<Chars<'a>>,
     cursor: usize,
     current_char: Option<char>,
+    /// The offset of the source within the whole program, used for spans.
+    offset: usize,
 }
 
 impl<'a> Lexer<'a> {
@@ -100,6 +171,16 @@ impl<'a> Lexer<'a> {
             chars,
             cursor: 0,
             current_char,
+            offset: 0,
+        }
+    }
+
+    /// Constructs a new [`Lexer`] instance from a source found at `offset` in the program, e.g.
+    /// an interpolated expression within a string.
+    pub(crate) fn with_offset(source: &'a str, offset: usize) -> Self {
+        Self {
+            offset,
+            ..Self::new(source)
         }
     }
 
@@ -124,6 +205,13 @@ impl<'a> Lexer<'a> {
         self.chars.peek()
     }
 
+    /// Reads the character after the one under the cursor without advancing the cursor.
+    fn peek_second_char(&self) -> Option<char> {
+        let mut chars = self.chars.clone();
+        chars.next();
+        chars.next()
+    }
+
     /// Reads the character under the cursor, advances the cursor, and
     /// updates the current character.
     fn read_char(&mut self) {
@@ -160,45 +248,125 @@ impl<'a> Lexer<'a> {
     }
 
     /// Reads a number, leaving the cursor at the last character of the number.
-    fn read_number(&mut self) -> &'a str {
+    ///
+    /// Numbers containing a decimal point are read as floats.
+    fn read_number(&mut self) -> TokenKind<'a> {
         let start = self.cursor;
+        let mut is_float = false;
 
         while let Some(ch) = self.peek_char() {
             if ch.is_ascii_digit() {
                 self.read_char();
+            } else if *ch == '.'
+                && !is_float
+                && self
+                    .peek_second_char()
+                    .is_some_and(|ch| ch.is_ascii_digit())
+            {
+                // A decimal point must be followed by a digit to be part of the number
+                is_float = true;
+                self.read_char();
             } else {
                 break;
             }
         }
 
-        &self.source[start..=self.cursor]
+        let number = &self.source[start..=self.cursor];
+
+        if is_float {
+            TokenKind::Float(number)
+        } else {
+            TokenKind::Int(number)
+        }
     }
 
     /// Reads a string, leaving the cursor at the last character of the string.
+    ///
+    /// Interpolated expressions are left unparsed in the returned slice.
     fn read_str(&mut self) -> Result<&'a str> {
         let start = self.cursor;
-        // Consume opening quote.
-        self.read_char();
 
-        loop {
-            match self.current_char {
-                Some(ch) if ch == '"' => {
-                    break;
+        let len = match find_str_end(&self.source[start + 1..]) {
+            Ok(len) => len,
+            Err(ScanError::Unterminated) => {
+                return Err(LexerError::UnterminatedString {
+                    span: self.span(start, 1),
                 }
-                None => {
-                    return Err(LexerError::UnterminatedString {
-                        span: (start, 1).into(),
-                    }
-                    .into())
+                .into())
+            }
+            Err(ScanError::UnclosedInterpolation(idx)) => {
+                return Err(LexerError::UnclosedInterpolation {
+                    span: self.span(start + 1 + idx, 1),
+                }
+                .into())
+            }
+            Err(ScanError::InvalidEscape(idx, len)) => {
+                let idx = start + 1 + idx;
+                return Err(LexerError::InvalidEscape {
+                    sequence: self.source[idx..idx + len].to_string(),
+                    span: self.span(idx, len),
                 }
-                _ => self.read_char(),
+                .into());
             }
+        };
+
+        // Advance to the closing quote.
+        while self.cursor < start + 1 + len {
+            self.read_char();
         }
 
         // Exclude the start and closing quotes in the slice.
         Ok(&self.source[start + 1..self.cursor])
     }
 
+    /// Reads a raw string, e.g. `r"foo"` or `r#"foo"#`, leaving the cursor at the last character
+    /// of the string.
+    ///
+    /// Raw strings contain neither escape sequences nor interpolated expressions.
+    fn read_raw_str(&mut self) -> Result<&'a str> {
+        let start = self.cursor;
+
+        // Any number of `#` may be used, so that the string can contain `"#`
+        let mut hashes = 0;
+        while let Some('#') = self.peek_char() {
+            self.read_char();
+            hashes += 1;
+        }
+
+        if self.peek_char() != Some(&'"') {
+            return Err(LexerError::InvalidTokenKind {
+                ch: '#',
+                span: self.span(self.cursor, 1),
+            }
+            .into());
+        }
+
+        // Consume the opening quote.
+        self.read_char();
+
+        let content_start = self.cursor + 1;
+        let terminator = format!("\"{}", "#".repeat(hashes));
+
+        let Some(len) = self.source[content_start..].find(&terminator) else {
+            return Err(LexerError::UnterminatedString {
+                span: self.span(start, 1),
+            }
+            .into());
+        };
+
+        // Advance to the last character of the terminator.
+        while self.cursor < content_start + len + hashes {
+            self.read_char();
+        }
+
+        Ok(&self.source[content_start..content_start + len])
+    }
+
+    /// Creates a span relative to the whole program.
+    fn span(&self, start: usize, len: usize) -> SourceSpan {
+        (self.offset + start, len).into()
+    }
+
     /// Consumes all whitespace characters until a non-whitespace character is read.
     fn consume_whitespace(&mut self) {
         while let Some(ch) = self.current_char {
@@ -230,13 +398,7 @@ impl<'a> Lexer<'a> {
                     self.read_char();
                     TokenKind::And
                 }
-                _ => {
-                    return Err(LexerError::InvalidTokenKind {
-                        ch: '&',
-                        span: (start + 1, 1).into(),
-                    }
-                    .into())
-                }
+                _ => TokenKind::Ampersand,
             },
             '|' => match self.peek_char() {
                 Some('|') => {
@@ -247,22 +409,50 @@ impl<'a> Lexer<'a> {
                     self.read_char();
                     TokenKind::Pipe
                 }
-                _ => {
-                    return Err(LexerError::InvalidTokenKind {
-                        ch: '|',
-                        span: (start + 1, 1).into(),
-                    }
-                    .into())
+                _ => TokenKind::Bar,
+            },
+            '+' => match self.peek_char() {
+                Some('=') => {
+                    self.read_char();
+                    TokenKind::CompoundAssignment(BinaryOp::Add)
+                }
+                _ => TokenKind::Plus,
+            },
+            '-' => match self.peek_char() {
+                Some('=') => {
+                    self.read_char();
+                    TokenKind::CompoundAssignment(BinaryOp::Sub)
                 }
+                _ => TokenKind::Minus,
             },
-            '+' => TokenKind::Plus,
-            '-' => TokenKind::Minus,
-            '*' => TokenKind::Star,
+            '*' => match self.peek_char() {
+                Some('*') => {
+                    self.read_char();
+                    TokenKind::DoubleStar
+                }
+                Some('=') => {
+                    self.read_char();
+                    TokenKind::CompoundAssignment(BinaryOp::Mul)
+                }
+                _ => TokenKind::Star,
+            },
+            '%' => match self.peek_char() {
+                Some('=') => {
+                    self.read_char();
+                    TokenKind::CompoundAssignment(BinaryOp::Rem)
+                }
+                _ => TokenKind::Percent,
+            },
+            '^' => TokenKind::Caret,
             '/' => match self.peek_char() {
                 Some('/') => {
                     self.read_comment();
                     return self.next();
                 }
+                Some('=') => {
+                    self.read_char();
+                    TokenKind::CompoundAssignment(BinaryOp::Div)
+                }
                 _ => TokenKind::Slash,
             },
             '=' => match self.peek_char() {
@@ -270,6 +460,10 @@ impl<'a> Lexer<'a> {
                     self.read_char();
                     TokenKind::RelOp(RelOp::Eq)
                 }
+                Some('>') => {
+                    self.read_char();
+                    TokenKind::FatArrow
+                }
                 _ => TokenKind::Assignment,
             },
             '!' => match self.peek_char() {
@@ -284,6 +478,10 @@ impl<'a> Lexer<'a> {
                     self.read_char();
                     TokenKind::RelOp(RelOp::LessEq)
                 }
+                Some('<') => {
+                    self.read_char();
+                    TokenKind::LeftShift
+                }
                 _ => TokenKind::RelOp(RelOp::Less),
             },
             '>' => match self.peek_char() {
@@ -291,31 +489,79 @@ impl<'a> Lexer<'a> {
                     self.read_char();
                     TokenKind::RelOp(RelOp::GreaterEq)
                 }
+                Some('>') => {
+                    self.read_char();
+                    TokenKind::RightShift
+                }
                 _ => TokenKind::RelOp(RelOp::Greater),
             },
             ',' => TokenKind::Separator,
+            ':' => TokenKind::Colon,
             '(' => TokenKind::LeftParen,
             ')' => TokenKind::RightParen,
             '{' => TokenKind::LeftBrace,
             '}' => TokenKind::RightBrace,
             '[' => TokenKind::LeftBracket,
             ']' => TokenKind::RightBracket,
-            '.' => TokenKind::Dot,
+            '#' => match self.peek_char() {
+                Some('{') => {
+                    self.read_char();
+                    TokenKind::LeftMapBrace
+                }
+                _ => {
+                    return Err(LexerError::InvalidTokenKind {
+                        ch: '#',
+                        span: self.span(start, 1),
+                    }
+                    .into())
+                }
+            },
+            '.' => match self.peek_char() {
+                Some('.') => {
+                    self.read_char();
+                    match self.peek_char() {
+                        Some('=') => {
+                            self.read_char();
+                            TokenKind::RangeInclusive
+                        }
+                        _ => TokenKind::Range,
+                    }
+                }
+                _ => TokenKind::Dot,
+            },
             '\n' => TokenKind::EndOfLine,
             '"' => TokenKind::Str(self.read_str()?),
-            '0'..='9' => TokenKind::Int(self.read_number()),
+            '\'' if self.peek_char().is_some_and(|ch| ch.is_ident()) => {
+                self.read_char();
+                TokenKind::Label(self.read_ident())
+            }
+            '0'..='9' => self.read_number(),
+            'r' if matches!(self.peek_char(), Some('"' | '#')) => {
+                TokenKind::RawStr(self.read_raw_str()?)
+            }
             ch if ch.is_ident() => {
                 let ident = self.read_ident();
 
                 match ident {
                     // Keywords
                     "let" => TokenKind::Let,
+                    "const" => TokenKind::Const,
                     "if" => TokenKind::Cond,
+                    "else" => TokenKind::Else,
                     "fn" => TokenKind::Func,
                     "return" => TokenKind::Return,
                     "loop" => TokenKind::Loop,
+                    "while" => TokenKind::While,
+                    "for" => TokenKind::For,
+                    "in" => TokenKind::In,
                     "continue" => TokenKind::Continue,
                     "break" => TokenKind::Break,
+                    "match" => TokenKind::Match,
+                    "struct" => TokenKind::Struct,
+                    "enum" => TokenKind::Enum,
+                    "throw" => TokenKind::Throw,
+                    "try" => TokenKind::Try,
+                    "catch" => TokenKind::Catch,
                     "use" => TokenKind::Use,
                     // Misc
                     "true" | "false" => TokenKind::Bool(ident),
@@ -325,7 +571,7 @@ impl<'a> Lexer<'a> {
             ch => {
                 return Err(LexerError::InvalidTokenKind {
                     ch,
-                    span: (start, 1).into(),
+                    span: self.span(start, 1),
                 }
                 .into());
             }
@@ -337,12 +583,116 @@ impl<'a> Lexer<'a> {
             return self.next();
         }
 
-        let span = SourceSpan::new(start.into(), self.cursor - start);
+        let span = self.span(start, self.cursor - start);
         let token = Token { kind, span };
         Ok(token)
     }
 }
 
+/// Reads an escape sequence from the start of `source`, i.e. following a `\\`.
+///
+/// Returns the escaped character and the length of the sequence, or the length of the sequence
+/// if it is invalid.
+pub(crate) fn read_escape(source: &str) -> Result<(char, usize), usize> {
+    let Some(ch) = source.chars().next() else {
+        return Err(0);
+    };
+
+    let ch = match ch {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        '0' => '\0',
+        '\\' => '\\',
+        '"' => '"',
+        '{' => '{',
+        '}' => '}',
+        // Unicode escapes, e.g. `\u{1F600}`
+        'u' => {
+            let Some(digits) = source.strip_prefix("u{") else {
+                return Err(1);
+            };
+
+            let end = digits
+                .find(|ch: char| !ch.is_ascii_hexdigit())
+                .unwrap_or(digits.len());
+
+            // Include the `u` and opening brace in the length
+            if !digits[end..].starts_with('}') {
+                return Err(end + 2);
+            }
+
+            // Include the closing brace in the length too
+            return u32::from_str_radix(&digits[..end], 16)
+                .ok()
+                .and_then(char::from_u32)
+                .map(|ch| (ch, end + 3))
+                .ok_or(end + 3);
+        }
+        ch => return Err(ch.len_utf8()),
+    };
+
+    Ok((ch, 1))
+}
+
+/// Finds the end of a string, i.e. the index of its closing `"`.
+///
+/// Escape sequences are validated, and interpolated expressions within the string are skipped
+/// over, so that they may contain strings of their own.
+pub(crate) fn find_str_end(source: &str) -> Result<usize, ScanError> {
+    let mut idx = 0;
+
+    while let Some(ch) = source[idx..].chars().next() {
+        match ch {
+            '"' => return Ok(idx),
+            '\\' => match read_escape(&source[idx + 1..]) {
+                Ok((_, len)) => idx += len,
+                // Include the `\\` in the span
+                Err(len) => return Err(ScanError::InvalidEscape(idx, len + 1)),
+            },
+            // Escaped brace
+            '{' if source[idx + 1..].starts_with('{') => idx += 1,
+            '{' => {
+                // Reaching the end of the source means that the brace is what was never closed
+                let len =
+                    find_interpolation_end(&source[idx + 1..]).map_err(|error| match error {
+                        ScanError::Unterminated => ScanError::UnclosedInterpolation(idx),
+                        error => error.shift(idx + 1),
+                    })?;
+                idx += 1 + len;
+            }
+            _ => (),
+        }
+        idx += ch.len_utf8();
+    }
+
+    Err(ScanError::Unterminated)
+}
+
+/// Finds the end of an interpolated expression, i.e. the index of its closing `}`.
+///
+/// Braces and strings nested within the expression are skipped over.
+pub(crate) fn find_interpolation_end(source: &str) -> Result<usize, ScanError> {
+    let mut idx = 0;
+    let mut depth = 0;
+
+    while let Some(ch) = source[idx..].chars().next() {
+        match ch {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Ok(idx),
+            '}' => depth -= 1,
+            '"' => {
+                let len = find_str_end(&source[idx + 1..]).map_err(|error| error.shift(idx + 1))?;
+                idx += 1 + len;
+            }
+            _ => (),
+        }
+        idx += ch.len_utf8();
+    }
+
+    Err(ScanError::Unterminated)
+}
+
 trait CharExt {
     fn is_ident(&self) -> bool;
 }
@@ -409,6 +759,70 @@ mod tests {
         )
     }
 
+    #[test]
+    fn float() {
+        let source = "3.14 1.foo";
+        let mut lexer = Lexer::new(source);
+        assert_eq!(
+            lexer.tokenize().unwrap(),
+            vec![
+                Token {
+                    kind: TokenKind::Float("3.14"),
+                    span: (0, 4).into()
+                },
+                Token {
+                    kind: TokenKind::Int("1"),
+                    span: (5, 1).into()
+                },
+                Token {
+                    kind: TokenKind::Dot,
+                    span: (6, 1).into()
+                },
+                Token {
+                    kind: TokenKind::Ident("foo"),
+                    span: (7, 3).into()
+                },
+            ],
+            "Decimal points should only be read as part of a number when followed by a digit"
+        )
+    }
+
+    #[test]
+    fn range() {
+        let source = "0..1 0..=1";
+        let mut lexer = Lexer::new(source);
+        assert_eq!(
+            lexer.tokenize().unwrap(),
+            vec![
+                Token {
+                    kind: TokenKind::Int("0"),
+                    span: (0, 1).into()
+                },
+                Token {
+                    kind: TokenKind::Range,
+                    span: (1, 2).into()
+                },
+                Token {
+                    kind: TokenKind::Int("1"),
+                    span: (3, 1).into()
+                },
+                Token {
+                    kind: TokenKind::Int("0"),
+                    span: (5, 1).into()
+                },
+                Token {
+                    kind: TokenKind::RangeInclusive,
+                    span: (6, 3).into()
+                },
+                Token {
+                    kind: TokenKind::Int("1"),
+                    span: (9, 1).into()
+                },
+            ],
+            "Ranges should not be read as part of a number"
+        )
+    }
+
     #[test]
     fn string() {
         let source = r#"("foo")"#;
@@ -433,6 +847,106 @@ mod tests {
         )
     }
 
+    #[test]
+    fn interpolated_string() {
+        let source = r#""foo {bar("}")} {{""#;
+        let mut lexer = Lexer::new(source);
+        assert_eq!(
+            lexer.tokenize().unwrap(),
+            vec![Token {
+                kind: TokenKind::Str(r#"foo {bar("}")} {{"#),
+                span: (0, 19).into()
+            }],
+            "Strings within interpolated expressions should not terminate the string"
+        )
+    }
+
+    #[test]
+    fn escaped_string() {
+        let source = r#""\"foo\" \u{1F600}""#;
+        let mut lexer = Lexer::new(source);
+        assert_eq!(
+            lexer.tokenize().unwrap(),
+            vec![Token {
+                kind: TokenKind::Str(r#"\"foo\" \u{1F600}"#),
+                span: (0, 19).into()
+            }],
+            "Escaped quotes should not terminate the string"
+        )
+    }
+
+    #[test]
+    fn invalid_escape() {
+        let source = r#""foo \q""#;
+        let mut lexer = Lexer::new(source);
+        let error = lexer.tokenize().expect_err("result should be an error");
+        assert!(matches!(
+            error.downcast_ref::<LexerError>(),
+            Some(LexerError::InvalidEscape { span, .. }) if *span == (5, 2).into()
+        ));
+    }
+
+    #[test]
+    fn invalid_unicode_escape() {
+        let source = r#""foo \u{110000}""#;
+        let mut lexer = Lexer::new(source);
+        let error = lexer.tokenize().expect_err("result should be an error");
+        assert!(matches!(
+            error.downcast_ref::<LexerError>(),
+            Some(LexerError::InvalidEscape { sequence, span })
+                if sequence == r"\u{110000}" && *span == (5, 10).into()
+        ));
+    }
+
+    #[test]
+    fn unclosed_interpolation() {
+        let source = r#""abc { def""#;
+        let mut lexer = Lexer::new(source);
+        let error = lexer.tokenize().expect_err("result should be an error");
+        assert!(matches!(
+            error.downcast_ref::<LexerError>(),
+            Some(LexerError::UnclosedInterpolation { span }) if *span == (5, 1).into()
+        ));
+    }
+
+    #[test]
+    fn raw_string() {
+        let source = r##"r#"{"foo": "\n"}"#"##;
+        let mut lexer = Lexer::new(source);
+        assert_eq!(
+            lexer.tokenize().unwrap(),
+            vec![Token {
+                kind: TokenKind::RawStr(r#"{"foo": "\n"}"#),
+                span: (0, 18).into()
+            }],
+            "Raw strings should only be terminated by a quote followed by the same number of `#`"
+        )
+    }
+
+    #[test]
+    fn label() {
+        let source = "'outer: loop";
+        let mut lexer = Lexer::new(source);
+        assert_eq!(
+            lexer.tokenize().unwrap(),
+            vec![
+                Token {
+                    kind: TokenKind::Label("outer"),
+                    span: (0, 6).into()
+                },
+                Token {
+                    kind: TokenKind::Colon,
+                    span: (6, 1).into()
+                },
+                Token {
+                    kind: TokenKind::Loop,
+                    span: (8, 4).into()
+                }
+            ],
+            "Labels should read properly"
+        )
+    }
+
     #[test]
     fn comment() {
         let source = "// foo = bar.baz(-1, 0)\nfoo";
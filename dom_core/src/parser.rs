@@ -5,11 +5,18 @@
 //! - Logical OR
 //! - Logical AND
 //! - Operators
+//! - Ranges
+//! - Bitwise OR
+//! - Bitwise XOR
+//! - Bitwise AND
+//! - Bitwise Shifts
 //! - Binary Addition
 //! - Binary Multiplication
 //! - Unary Operators
-//! - Function Call
+//! - Exponents
+//! - Function Call, Indexing
 //! - Lists
+//! - Maps
 //! - Primary Expressions
 
 use std::collections::VecDeque;
@@ -18,9 +25,11 @@ use miette::{Diagnostic, Result, SourceSpan};
 use thiserror::Error;
 
 use crate::ast::{
-    BinaryOp, Cond, Expr, ExprKind, Func, Ident, LogicOp, Loop, Stmt, UnaryOp, Use, Var,
+    Alternate, BinaryOp, Cond, Enum, Expr, ExprKind, For, Func, Ident, LogicOp, Loop, MatchArm,
+    NamedArg, Param, Pattern, PatternKind, Stmt, Struct, UnaryOp, Use, Var, Variant, While,
 };
-use crate::lexer::{Lexer, Token, TokenKind};
+use crate::lexer::{find_interpolation_end, read_escape, Lexer, Token, TokenKind};
+use crate::stack;
 
 #[derive(Error, Diagnostic, Debug)]
 pub(crate) enum ParserError {
@@ -36,6 +45,24 @@ pub(crate) enum ParserError {
         #[label("this conditional is missing a `}}` to end its body")]
         span: SourceSpan,
     },
+    #[error("expected right brace `}}` to end block")]
+    #[diagnostic(code(parser::block_end))]
+    BlockEnd {
+        #[label("this block is missing a `}}` to end its body")]
+        span: SourceSpan,
+    },
+    #[error("expected left brace `{{` following `else` keyword")]
+    #[diagnostic(code(parser::else_block_begin))]
+    ElseBlockBegin {
+        #[label("this `else` is missing a `{{` to start its body")]
+        span: SourceSpan,
+    },
+    #[error("expected right brace `}}` to end `else` block")]
+    #[diagnostic(code(parser::else_block_end))]
+    ElseBlockEnd {
+        #[label("this `else` is missing a `}}` to end its body")]
+        span: SourceSpan,
+    },
     #[error("invalid identifier following `fn` keyword")]
     #[diagnostic(code(parser::fn_identifier))]
     FnIdentifier {
@@ -48,12 +75,6 @@ pub(crate) enum ParserError {
         #[label("expected `(` following this identifier")]
         span: SourceSpan,
     },
-    #[error("expected only identifiers in function arguments")]
-    #[diagnostic(code(parser::fn_args))]
-    FnArgs {
-        #[label("this function has non-identifier arguments")]
-        span: SourceSpan,
-    },
     #[error("expected right parenthesis `)` to end argument list")]
     #[diagnostic(code(parser::fn_args_end))]
     FnArgsEnd {
@@ -72,22 +93,162 @@ pub(crate) enum ParserError {
         #[label("this function is missing a `}}` to end its body")]
         span: SourceSpan,
     },
+    #[error("invalid identifier following `struct` keyword")]
+    #[diagnostic(code(parser::struct_identifier))]
+    StructIdentifier {
+        #[label("invalid identifier here")]
+        span: SourceSpan,
+    },
+    #[error("expected left brace `{{` following struct identifier")]
+    #[diagnostic(code(parser::struct_block_begin))]
+    StructBlockBegin {
+        #[label("this struct is missing a `{{` to start its fields")]
+        span: SourceSpan,
+    },
+    #[error("expected right brace `}}` to end struct fields")]
+    #[diagnostic(code(parser::struct_block_end))]
+    StructBlockEnd {
+        #[label("this struct is missing a `}}` to end its fields")]
+        span: SourceSpan,
+    },
+    #[error("invalid struct field")]
+    #[diagnostic(code(parser::struct_field))]
+    StructField {
+        #[label("expected a field name here")]
+        span: SourceSpan,
+    },
+    #[error("duplicate struct field")]
+    #[diagnostic(code(parser::duplicate_struct_field))]
+    DuplicateStructField {
+        #[label("this field is already declared")]
+        span: SourceSpan,
+    },
+    #[error("invalid identifier following `enum` keyword")]
+    #[diagnostic(code(parser::enum_identifier))]
+    EnumIdentifier {
+        #[label("invalid identifier here")]
+        span: SourceSpan,
+    },
+    #[error("expected left brace `{{` following enum identifier")]
+    #[diagnostic(code(parser::enum_block_begin))]
+    EnumBlockBegin {
+        #[label("this enum is missing a `{{` to start its variants")]
+        span: SourceSpan,
+    },
+    #[error("expected right brace `}}` to end enum variants")]
+    #[diagnostic(code(parser::enum_block_end))]
+    EnumBlockEnd {
+        #[label("this enum is missing a `}}` to end its variants")]
+        span: SourceSpan,
+    },
+    #[error("invalid enum variant")]
+    #[diagnostic(code(parser::enum_variant))]
+    EnumVariant {
+        #[label("expected a variant name here")]
+        span: SourceSpan,
+    },
+    #[error("duplicate enum variant")]
+    #[diagnostic(code(parser::duplicate_enum_variant))]
+    DuplicateEnumVariant {
+        #[label("this variant is already declared")]
+        span: SourceSpan,
+    },
+    #[error("invalid enum variant payload field")]
+    #[diagnostic(code(parser::variant_field))]
+    VariantField {
+        #[label("expected a field name here")]
+        span: SourceSpan,
+    },
+    #[error("expected right parenthesis `)` to end variant payload")]
+    #[diagnostic(code(parser::variant_fields_end))]
+    VariantFieldsEnd {
+        #[label("this variant is missing a `)` to end its payload")]
+        span: SourceSpan,
+    },
+    #[error("expected right parenthesis `)` to end variant pattern")]
+    #[diagnostic(code(parser::variant_pattern_end))]
+    VariantPatternEnd {
+        #[label("this pattern is missing a `)` to end its payload")]
+        span: SourceSpan,
+    },
     #[error("expected right bracket `]` to end list")]
     #[diagnostic(code(parser::fn_block_end))]
     ListItemsEnd {
         #[label("this list is missing a `]` to terminate")]
         span: SourceSpan,
     },
-    #[error("invalid identifier following `let` keyword")]
-    #[diagnostic(code(parser::var_identifier))]
-    VarIdentifier {
-        #[label("invalid identifier here")]
+    #[error("expected colon `:` following map key")]
+    #[diagnostic(code(parser::map_entry_colon))]
+    MapEntryColon {
+        #[label("expected `:` following this key")]
+        span: SourceSpan,
+    },
+    #[error("expected right brace `}}` to end map")]
+    #[diagnostic(code(parser::map_entries_end))]
+    MapEntriesEnd {
+        #[label("this map is missing a `}}` to terminate")]
+        span: SourceSpan,
+    },
+    #[error("interpolated expression is empty")]
+    #[diagnostic(
+        code(parser::empty_interpolation),
+        help("use `{{{{` to include a literal `{{` in a string")
+    )]
+    EmptyInterpolation {
+        #[label("expected an expression here")]
         span: SourceSpan,
     },
-    #[error("expected assignment operator `=` following identifier in variable declaration")]
+    #[error("expected assignment operator `=` following pattern in variable declaration")]
     #[diagnostic(code(parser::var_assignment))]
     VarAssignment {
-        #[label("expected `=` following this identifier")]
+        #[label("expected `=` following this pattern")]
+        span: SourceSpan,
+    },
+    #[error("rest parameter cannot have a default value")]
+    #[diagnostic(
+        code(parser::rest_default),
+        help("a rest parameter is an empty list if no arguments are left over")
+    )]
+    RestDefault {
+        #[label("this rest parameter has a default value")]
+        span: SourceSpan,
+    },
+    #[error("positional argument follows named arguments")]
+    #[diagnostic(
+        code(parser::positional_after_named),
+        help("named arguments must come after all positional arguments")
+    )]
+    PositionalAfterNamed {
+        #[label("this argument must come before any named arguments")]
+        span: SourceSpan,
+    },
+    #[error("argument `{ident}` is passed more than once")]
+    #[diagnostic(code(parser::duplicate_named_arg))]
+    DuplicateNamedArg {
+        ident: Ident,
+        #[label("this argument has already been passed")]
+        span: SourceSpan,
+    },
+    #[error("expected colon `:` following loop label")]
+    #[diagnostic(code(parser::label_colon))]
+    LabelColon {
+        #[label("this label is missing a `:`")]
+        span: SourceSpan,
+    },
+    #[error("expected loop following label")]
+    #[diagnostic(
+        code(parser::label_loop),
+        help("only `loop`, `while` and `for` loops can be labeled")
+    )]
+    LabelLoop {
+        #[label("this label is not followed by a loop")]
+        span: SourceSpan,
+    },
+    #[error("use of undeclared label `'{label}`")]
+    #[diagnostic(code(parser::undeclared_label))]
+    UndeclaredLabel {
+        label: Ident,
+        #[label("no enclosing loop has this label")]
         span: SourceSpan,
     },
     #[error("expected left brace `{{` following loop statement")]
@@ -102,6 +263,42 @@ pub(crate) enum ParserError {
         #[label("this loop is missing a `}}` to end its body")]
         span: SourceSpan,
     },
+    #[error("expected left brace `{{` following while loop")]
+    #[diagnostic(code(parser::while_block_begin))]
+    WhileBlockBegin {
+        #[label("this loop is missing a `{{` to start its body")]
+        span: SourceSpan,
+    },
+    #[error("expected right brace `}}` to end while loop block")]
+    #[diagnostic(code(parser::while_block_end))]
+    WhileBlockEnd {
+        #[label("this loop is missing a `}}` to end its body")]
+        span: SourceSpan,
+    },
+    #[error("invalid identifier following `for` keyword")]
+    #[diagnostic(code(parser::for_identifier))]
+    ForIdentifier {
+        #[label("invalid identifier here")]
+        span: SourceSpan,
+    },
+    #[error("expected `in` keyword following identifier in for loop")]
+    #[diagnostic(code(parser::for_in))]
+    ForIn {
+        #[label("expected `in` following this identifier")]
+        span: SourceSpan,
+    },
+    #[error("expected left brace `{{` following for loop")]
+    #[diagnostic(code(parser::for_block_begin))]
+    ForBlockBegin {
+        #[label("this loop is missing a `{{` to start its body")]
+        span: SourceSpan,
+    },
+    #[error("expected right brace `}}` to end for loop block")]
+    #[diagnostic(code(parser::for_block_end))]
+    ForBlockEnd {
+        #[label("this loop is missing a `}}` to end its body")]
+        span: SourceSpan,
+    },
     #[error("unexpected token in import")]
     #[diagnostic(
         code(parser::use_non_ident),
@@ -111,6 +308,113 @@ pub(crate) enum ParserError {
         #[label("this token is not supported as a module name")]
         span: SourceSpan,
     },
+    #[error("expected right bracket `]` to end index")]
+    #[diagnostic(code(parser::index_end))]
+    IndexEnd {
+        #[label("this index is missing a `]`")]
+        span: SourceSpan,
+    },
+    #[error("expected left brace `{{` following match expression")]
+    #[diagnostic(code(parser::match_block_begin))]
+    MatchBlockBegin {
+        #[label("this match is missing a `{{` to start its arms")]
+        span: SourceSpan,
+    },
+    #[error("expected right brace `}}` to end match arms")]
+    #[diagnostic(code(parser::match_block_end))]
+    MatchBlockEnd {
+        #[label("this match is missing a `}}` to end its arms")]
+        span: SourceSpan,
+    },
+    #[error("expected fat arrow `=>` following match pattern")]
+    #[diagnostic(code(parser::match_arm_arrow))]
+    MatchArmArrow {
+        #[label("this pattern is missing a `=>`")]
+        span: SourceSpan,
+    },
+    #[error("expected right brace `}}` to end match arm")]
+    #[diagnostic(code(parser::match_arm_end))]
+    MatchArmEnd {
+        #[label("this arm is missing a `}}` to end its body")]
+        span: SourceSpan,
+    },
+    #[error("expected left brace `{{` following `try` keyword")]
+    #[diagnostic(code(parser::try_block_begin))]
+    TryBlockBegin {
+        #[label("this try is missing a `{{` to start its body")]
+        span: SourceSpan,
+    },
+    #[error("expected right brace `}}` to end try block")]
+    #[diagnostic(code(parser::try_block_end))]
+    TryBlockEnd {
+        #[label("this try is missing a `}}` to end its body")]
+        span: SourceSpan,
+    },
+    #[error("expected `catch` following try block")]
+    #[diagnostic(code(parser::try_catch))]
+    TryCatch {
+        #[label("this try is missing a `catch` block")]
+        span: SourceSpan,
+    },
+    #[error("expected left brace `{{` following `catch` keyword")]
+    #[diagnostic(code(parser::catch_block_begin))]
+    CatchBlockBegin {
+        #[label("this catch is missing a `{{` to start its body")]
+        span: SourceSpan,
+    },
+    #[error("expected right brace `}}` to end catch block")]
+    #[diagnostic(code(parser::catch_block_end))]
+    CatchBlockEnd {
+        #[label("this catch is missing a `}}` to end its body")]
+        span: SourceSpan,
+    },
+    #[error("invalid pattern")]
+    #[diagnostic(
+        code(parser::invalid_pattern),
+        help("patterns can be literals, identifiers, `_` or lists of patterns")
+    )]
+    InvalidPattern {
+        #[label("this is not a valid pattern")]
+        span: SourceSpan,
+    },
+    #[error("expected right bracket `]` to end list pattern")]
+    #[diagnostic(code(parser::list_pattern_end))]
+    ListPatternEnd {
+        #[label("this list pattern is missing a `]`")]
+        span: SourceSpan,
+    },
+    #[error("rest pattern `..` is misplaced")]
+    #[diagnostic(code(parser::misplaced_rest))]
+    MisplacedRest {
+        #[label(
+            "rest patterns may only be used once within a list pattern, or as the last parameter"
+        )]
+        span: SourceSpan,
+    },
+    #[error("unexpected end of input")]
+    #[diagnostic(code(parser::unexpected_end))]
+    UnexpectedEnd {
+        #[label("expected an expression here")]
+        span: SourceSpan,
+    },
+    #[error("program is nested too deeply")]
+    #[diagnostic(
+        code(parser::nesting_too_deep),
+        help("at most {MAX_NESTING} levels of nesting are supported")
+    )]
+    NestingTooDeep {
+        #[label("this exceeds the maximum nesting depth")]
+        span: SourceSpan,
+    },
+    #[error("expression chain is too long")]
+    #[diagnostic(
+        code(parser::chain_too_long),
+        help("at most {MAX_CHAIN} operations can be chained")
+    )]
+    ChainTooLong {
+        #[label("this exceeds the maximum chain length")]
+        span: SourceSpan,
+    },
     #[error("token is unsupported")]
     #[diagnostic(code(parser::unsupported_token))]
     Unsupported {
@@ -128,8 +432,26 @@ enum Process {
 pub struct Parser<'a> {
     tokens: VecDeque<Token<'a>>,
     source: &'a str,
+    /// The offset of the source within the whole program.
+    offset: usize,
+    /// The labels of the loops enclosing the current position.
+    labels: Vec<Ident>,
+    /// The current nesting depth of statements, expressions and patterns.
+    depth: usize,
+    /// The number of chain links enclosing the current position, e.g. the `+` in `a + b`.
+    links: usize,
 }
 
+/// The maximum nesting depth of statements, expressions and patterns.
+const MAX_NESTING: usize = 128;
+
+/// The maximum number of links in chains such as `a + b + c`, including the links of any chains
+/// enclosing them.
+///
+/// Chains are parsed iteratively, but still nest the syntax tree a level deeper with every link.
+/// Dropping the syntax tree doesn't guard against overflowing the stack, so its depth is limited.
+const MAX_CHAIN: usize = 4096;
+
 impl<'a> Parser<'a> {
     #[must_use]
     pub fn new(source: &'a str) -> Self {
@@ -150,6 +472,47 @@ impl<'a> Parser<'a> {
         Ok(program)
     }
 
+    /// Runs `parse` one level of nesting deeper, failing if the maximum nesting depth is exceeded.
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= MAX_NESTING {
+            return Err(ParserError::NestingTooDeep {
+                span: self.next_span(),
+            }
+            .into());
+        }
+
+        self.depth += 1;
+        let result = stack::guard(|| parse(self));
+        self.depth -= 1;
+
+        result
+    }
+
+    /// Adds a link to the chain being parsed, failing if the maximum chain length is exceeded.
+    ///
+    /// Chains such as `a + b + c` are parsed iteratively, so the number of links is only reset once
+    /// the whole chain is parsed.
+    fn link(&mut self) -> Result<()> {
+        if self.links >= MAX_CHAIN {
+            return Err(ParserError::ChainTooLong {
+                span: self.next_span(),
+            }
+            .into());
+        }
+
+        self.links += 1;
+
+        Ok(())
+    }
+
+    /// Returns the span of the next token, or the end of the source if there are none left.
+    fn next_span(&self) -> SourceSpan {
+        self.peek().map_or_else(
+            || (self.offset + self.source.len(), 0).into(),
+            |token| token.span,
+        )
+    }
+
     fn process<F>(&mut self, mut p: F) -> Result<Vec<Stmt>>
     where
         F: FnMut(&TokenKind<'a>) -> Process,
@@ -160,7 +523,7 @@ impl<'a> Parser<'a> {
             match p(&token.kind) {
                 Process::Break => break,
                 Process::Push => {
-                    body.push(self.parse_stmt()?);
+                    body.push(self.nested(Self::parse_stmt)?);
                 }
             }
         }
@@ -176,20 +539,26 @@ impl<'a> Parser<'a> {
         self.peek().map(|token| &token.kind)
     }
 
+    fn peek_nth_kind(&self, n: usize) -> Option<&TokenKind<'a>> {
+        self.tokens.get(n).map(|token| &token.kind)
+    }
+
     fn consume(&mut self) -> Token<'a> {
         self.tokens.pop_front().expect("tokens should not be empty")
     }
 
-    fn expect(&mut self, kind: &TokenKind<'a>, error: ParserError) -> Result<()> {
+    fn expect(&mut self, kind: &TokenKind<'a>, error: ParserError) -> Result<Token<'a>> {
         if self.tokens.is_empty() {
             return Err(error.into());
         }
 
-        if &self.consume().kind != kind {
+        let token = self.consume();
+
+        if &token.kind != kind {
             return Err(error.into());
         }
 
-        Ok(())
+        Ok(token)
     }
 
     fn parse_stmt(&mut self) -> Result<Stmt> {
@@ -199,9 +568,24 @@ impl<'a> Parser<'a> {
 
         let stmt = match token.kind {
             TokenKind::Let => Stmt::Var(self.parse_var()?),
-            TokenKind::Cond => Stmt::Cond(self.parse_cond()?),
-            TokenKind::Func => Stmt::Func(self.parse_func()?),
-            TokenKind::Loop => Stmt::Loop(self.parse_loop()?),
+            TokenKind::Const => Stmt::Const(self.parse_var()?),
+            // Block-like expressions end the statement unless piped, so that e.g. a `-1` on the
+            // following line isn't parsed as a subtraction
+            TokenKind::Cond
+            | TokenKind::LeftBrace
+            | TokenKind::Loop
+            | TokenKind::While
+            | TokenKind::For
+            | TokenKind::Label(_) => {
+                let expr = self.parse_primary_expr()?;
+                Stmt::Expr(self.parse_pipes(expr)?)
+            }
+            // Anonymous functions are expressions
+            TokenKind::Func if matches!(self.peek_nth_kind(1), Some(TokenKind::Ident(_))) => {
+                Stmt::Func(self.parse_func()?)
+            }
+            TokenKind::Struct => Stmt::Struct(self.parse_struct()?),
+            TokenKind::Enum => Stmt::Enum(self.parse_enum()?),
             TokenKind::Use => Stmt::Use(self.parse_use()?),
             _ => Stmt::Expr(self.parse_expr()?),
         };
@@ -246,10 +630,47 @@ impl<'a> Parser<'a> {
         Ok(Use { path, span })
     }
 
-    fn parse_loop(&mut self) -> Result<Loop> {
-        // Consume the `loop` keyword
-        let span = self.consume().span;
+    /// Parses a loop following its keyword, or following its label if `start` is the span of
+    /// the label.
+    fn parse_loop_expr(
+        &mut self,
+        kind: TokenKind,
+        span: SourceSpan,
+        label: Option<Ident>,
+        start: SourceSpan,
+    ) -> Result<Expr> {
+        // The body of a labeled loop can refer to its label
+        if let Some(label) = &label {
+            self.labels.push(label.clone());
+        }
+
+        let result = match kind {
+            TokenKind::Loop => self
+                .parse_loop(span, label.clone())
+                .map(|(node, end)| (ExprKind::Loop(Box::new(node)), end)),
+            TokenKind::While => self
+                .parse_while(span, label.clone())
+                .map(|(node, end)| (ExprKind::While(Box::new(node)), end)),
+            TokenKind::For => self
+                .parse_for(span, label.clone())
+                .map(|(node, end)| (ExprKind::For(Box::new(node)), end)),
+            _ => unreachable!("loop expression should begin with a loop keyword"),
+        };
+
+        if label.is_some() {
+            self.labels.pop();
+        }
+
+        let (kind, end) = result?;
+
+        Ok(Expr {
+            kind,
+            span: start.extend(end),
+        })
+    }
 
+    fn parse_loop(&mut self, span: SourceSpan, label: Option<Ident>) -> Result<(Loop, SourceSpan)> {
+        // The `loop` keyword has already been consumed
         self.expect(&TokenKind::LeftBrace, ParserError::LoopBlockBegin { span })?;
 
         let body = self.process(|token| match token {
@@ -257,545 +678,2305 @@ impl<'a> Parser<'a> {
             _ => Process::Push,
         })?;
 
-        self.expect(&TokenKind::RightBrace, ParserError::LoopBlockEnd { span })?;
+        let end = self.expect(&TokenKind::RightBrace, ParserError::LoopBlockEnd { span })?;
 
-        Ok(Loop { body, span })
+        Ok((Loop { label, body, span }, end.span))
     }
 
-    fn parse_func(&mut self) -> Result<Func> {
-        // Consume the `fn` keyword
-        self.consume();
+    fn parse_while(
+        &mut self,
+        span: SourceSpan,
+        label: Option<Ident>,
+    ) -> Result<(While, SourceSpan)> {
+        // The `while` keyword has already been consumed
+        let condition = self.parse_expr()?;
 
-        let ident_token = self.consume();
-        let span = ident_token.span;
+        self.expect(&TokenKind::LeftBrace, ParserError::WhileBlockBegin { span })?;
 
-        let TokenKind::Ident(ident) = ident_token.kind else {
-            return Err(ParserError::FnIdentifier { span }.into());
-        };
+        let body = self.process(|token| match token {
+            TokenKind::RightBrace => Process::Break,
+            _ => Process::Push,
+        })?;
 
-        self.expect(&TokenKind::LeftParen, ParserError::FnArgsBegin { span })?;
+        let end = self.expect(&TokenKind::RightBrace, ParserError::WhileBlockEnd { span })?;
+
+        let node = While {
+            label,
+            condition,
+            body,
+            span,
+        };
 
-        let (args, last) = self.parse_args(&TokenKind::RightParen)?;
-        let last = last.unwrap_or(span.offset());
+        Ok((node, end.span))
+    }
 
-        let params: Result<Vec<Ident>, ()> = args
-            .into_iter()
-            .map(|expr| match expr.kind {
-                ExprKind::Ident(ident) => Ok(ident),
-                _ => Err(()),
-            })
-            .collect();
+    fn parse_for(&mut self, span: SourceSpan, label: Option<Ident>) -> Result<(For, SourceSpan)> {
+        // The `for` keyword has already been consumed
+        let ident_token = self.consume();
 
-        let Ok(params) = params else {
-            let span = (span.offset(), last - span.offset() + 1).into();
-            return Err(ParserError::FnArgs { span }.into());
+        let TokenKind::Ident(ident) = ident_token.kind else {
+            return Err(ParserError::ForIdentifier {
+                span: ident_token.span,
+            }
+            .into());
         };
 
-        self.expect(&TokenKind::RightParen, ParserError::FnArgsEnd { span })?;
+        self.expect(
+            &TokenKind::In,
+            ParserError::ForIn {
+                span: ident_token.span,
+            },
+        )?;
 
-        self.expect(&TokenKind::LeftBrace, ParserError::FnBlockBegin { span })?;
+        let iterable = self.parse_expr()?;
+
+        self.expect(&TokenKind::LeftBrace, ParserError::ForBlockBegin { span })?;
 
         let body = self.process(|token| match token {
             TokenKind::RightBrace => Process::Break,
             _ => Process::Push,
         })?;
 
-        self.expect(&TokenKind::RightBrace, ParserError::FnBlockEnd { span })?;
+        let end = self.expect(&TokenKind::RightBrace, ParserError::ForBlockEnd { span })?;
 
-        let func = Func {
+        let node = For {
+            label,
             ident: ident.to_string(),
-            params,
+            iterable,
             body,
             span,
         };
 
-        Ok(func)
+        Ok((node, end.span))
     }
 
-    fn parse_args(&mut self, end: &TokenKind<'a>) -> Result<(Vec<Expr>, Option<usize>)> {
-        let mut args = Vec::new();
-
-        if self.peek_kind() == Some(end) {
-            return Ok((args, None));
-        }
+    /// Parses the label following `break` or `continue`, if any, which must belong to an
+    /// enclosing loop.
+    fn parse_jump_label(&mut self) -> Result<(Option<Ident>, Option<SourceSpan>)> {
+        let Some(&TokenKind::Label(label)) = self.peek_kind() else {
+            return Ok((None, None));
+        };
 
-        // To keep track of the last column of the last [`Expr`]
-        let mut last;
-        loop {
-            // First argument won't be preceded by a separator
-            let arg = self.parse_expr()?;
-            last = Some(arg.span.offset() + arg.span.len());
-            args.push(arg);
+        let span = self.consume().span;
 
-            // Subsequent arguments will be
-            if self.peek_kind() == Some(&TokenKind::Separator) {
-                self.consume();
-            } else {
-                break;
+        if !self.labels.iter().any(|existing| existing == label) {
+            return Err(ParserError::UndeclaredLabel {
+                label: label.to_string(),
+                span,
             }
+            .into());
         }
 
-        Ok((args, last))
+        Ok((Some(label.to_string()), Some(span)))
     }
 
-    fn parse_cond(&mut self) -> Result<Cond> {
-        // Consume the `if` keyword
+    fn parse_func(&mut self) -> Result<Func> {
+        // Consume the `fn` keyword
         self.consume();
 
-        let condition = self.parse_expr()?;
-        let span = condition.span;
-
-        self.expect(&TokenKind::LeftBrace, ParserError::CondBlockBegin { span })?;
+        let ident_token = self.consume();
+        let span = ident_token.span;
 
-        let body = self.process(|token| match token {
-            TokenKind::RightBrace => Process::Break,
-            _ => Process::Push,
-        })?;
+        let TokenKind::Ident(ident) = ident_token.kind else {
+            return Err(ParserError::FnIdentifier { span }.into());
+        };
 
-        self.expect(&TokenKind::RightBrace, ParserError::CondBlockEnd { span })?;
+        let (params, body, _) = self.parse_func_signature(span)?;
 
-        let cond = Cond {
-            condition,
+        let func = Func {
+            ident: ident.to_string(),
+            params,
             body,
             span,
         };
 
-        Ok(cond)
+        Ok(func)
     }
 
-    fn parse_var(&mut self) -> Result<Var> {
-        // Consume the `let` keyword
+    fn parse_struct(&mut self) -> Result<Struct> {
+        // Consume the `struct` keyword
         self.consume();
 
         let ident_token = self.consume();
+        let span = ident_token.span;
 
         let TokenKind::Ident(ident) = ident_token.kind else {
-            return Err(ParserError::VarIdentifier {
-                span: ident_token.span,
-            }
-            .into());
+            return Err(ParserError::StructIdentifier { span }.into());
         };
 
         self.expect(
-            &TokenKind::Assignment,
-            ParserError::VarAssignment {
-                span: ident_token.span,
-            },
+            &TokenKind::LeftBrace,
+            ParserError::StructBlockBegin { span },
         )?;
 
-        let var = Var {
-            ident: ident.to_string(),
-            value: Box::new(self.parse_expr()?.into()),
-            span: ident_token.span,
-        };
-
-        Ok(var)
-    }
+        let mut fields: Vec<Ident> = Vec::new();
 
-    fn parse_expr(&mut self) -> Result<Expr> {
-        self.parse_assignment_expr()
-    }
+        while let Some(&Token { kind, span }) = self.peek() {
+            let field = match kind {
+                TokenKind::RightBrace => break,
+                TokenKind::Ident(field) => field.to_string(),
+                _ => return Err(ParserError::StructField { span }.into()),
+            };
 
-    fn parse_assignment_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_pipe_expr()?;
+            if fields.contains(&field) {
+                return Err(ParserError::DuplicateStructField { span }.into());
+            }
 
-        if self.peek_kind() == Some(&TokenKind::Assignment) {
             self.consume();
+            fields.push(field);
 
-            let right = self.parse_pipe_expr()?;
-            let span = left.span.extend(right.span);
-
-            left = Expr {
-                kind: ExprKind::Assignment {
-                    assignee: Box::new(left),
-                    value: Box::new(right),
-                },
-                span,
+            // Fields may optionally be separated by commas
+            if self.peek_kind() == Some(&TokenKind::Separator) {
+                self.consume();
             }
         }
 
-        Ok(left)
+        self.expect(&TokenKind::RightBrace, ParserError::StructBlockEnd { span })?;
+
+        Ok(Struct {
+            ident: ident.to_string(),
+            fields,
+            span,
+        })
     }
 
-    fn parse_pipe_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_logical_or_expr()?;
+    fn parse_enum(&mut self) -> Result<Enum> {
+        // Consume the `enum` keyword
+        self.consume();
 
-        while let Some(&TokenKind::Pipe) = self.peek_kind() {
-            // Consume the operator
-            self.consume();
+        let ident_token = self.consume();
+        let span = ident_token.span;
 
-            let right = self.parse_logical_or_expr()?;
-            let span = left.span.extend(right.span);
+        let TokenKind::Ident(ident) = ident_token.kind else {
+            return Err(ParserError::EnumIdentifier { span }.into());
+        };
 
-            left = Expr {
-                kind: ExprKind::Pipe {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                },
-                span,
-            }
-        }
+        self.expect(&TokenKind::LeftBrace, ParserError::EnumBlockBegin { span })?;
 
-        Ok(left)
-    }
+        let mut variants: Vec<Variant> = Vec::new();
 
-    fn parse_logical_or_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_logical_and_expr()?;
+        while let Some(&Token { kind, span }) = self.peek() {
+            let variant = match kind {
+                TokenKind::RightBrace => break,
+                TokenKind::Ident(variant) => variant.to_string(),
+                _ => return Err(ParserError::EnumVariant { span }.into()),
+            };
+
+            if variants.iter().any(|(existing, _)| *existing == variant) {
+                return Err(ParserError::DuplicateEnumVariant { span }.into());
+            }
 
-        while let Some(&TokenKind::Or) = self.peek_kind() {
-            // Consume the operator
             self.consume();
 
-            let right = self.parse_logical_and_expr()?;
-            let span = left.span.extend(right.span);
+            // Variants may carry a payload, e.g. `Circle(radius)`
+            let mut fields = Vec::new();
 
-            left = Expr {
-                kind: ExprKind::LogicOp {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                    op: LogicOp::Or,
-                },
-                span,
-            }
-        }
+            if self.peek_kind() == Some(&TokenKind::LeftParen) {
+                self.consume();
 
-        Ok(left)
-    }
+                while let Some(&Token { kind, span }) = self.peek() {
+                    match kind {
+                        TokenKind::RightParen => break,
+                        TokenKind::Ident(field) => fields.push(field.to_string()),
+                        _ => return Err(ParserError::VariantField { span }.into()),
+                    }
 
-    fn parse_logical_and_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_rel_expr()?;
+                    self.consume();
 
-        while let Some(&TokenKind::And) = self.peek_kind() {
-            // Consume the operator
-            self.consume();
+                    if self.peek_kind() == Some(&TokenKind::Separator) {
+                        self.consume();
+                    }
+                }
 
-            let right = self.parse_rel_expr()?;
-            let span = left.span.extend(right.span);
+                self.expect(
+                    &TokenKind::RightParen,
+                    ParserError::VariantFieldsEnd { span },
+                )?;
+            }
 
-            left = Expr {
-                kind: ExprKind::LogicOp {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                    op: LogicOp::And,
-                },
-                span,
+            variants.push((variant, fields));
+
+            // Variants may optionally be separated by commas
+            if self.peek_kind() == Some(&TokenKind::Separator) {
+                self.consume();
             }
         }
 
-        Ok(left)
+        self.expect(&TokenKind::RightBrace, ParserError::EnumBlockEnd { span })?;
+
+        Ok(Enum {
+            ident: ident.to_string(),
+            variants,
+            span,
+        })
     }
 
-    fn parse_rel_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_additive_expr()?;
+    fn parse_lambda(&mut self, span: SourceSpan) -> Result<Expr> {
+        // The `fn` keyword has already been consumed
+        let (params, body, end) = self.parse_func_signature(span)?;
 
-        if let Some(&TokenKind::RelOp(op)) = self.peek_kind() {
-            // Consume the operator
-            self.consume();
+        Ok(Expr {
+            kind: ExprKind::Lambda { params, body },
+            span: span.extend(end.span),
+        })
+    }
 
-            let right = self.parse_additive_expr()?;
-            let span = left.span.extend(right.span);
+    /// Parses the parameters and body of a function, returning the closing brace token.
+    fn parse_func_signature(
+        &mut self,
+        span: SourceSpan,
+    ) -> Result<(Vec<Param>, Vec<Stmt>, Token<'a>)> {
+        // Loops outside of the function can't be jumped out of from within it. Parsing stops at
+        // the first error, so the labels only need restoring on success
+        let labels = std::mem::take(&mut self.labels);
 
-            left = Expr {
-                kind: ExprKind::RelOp {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                    op,
-                },
-                span,
+        self.expect(&TokenKind::LeftParen, ParserError::FnArgsBegin { span })?;
+
+        let mut params = Vec::new();
+
+        while !matches!(self.peek_kind(), Some(TokenKind::RightParen) | None) {
+            // Only the last parameter can be a rest parameter
+            if let Some(rest) = params
+                .last()
+                .filter(|param: &&Param| matches!(param.pattern.kind, PatternKind::Rest(_)))
+            {
+                return Err(ParserError::MisplacedRest {
+                    span: rest.pattern.span,
+                }
+                .into());
             }
-        }
 
-        Ok(left)
-    }
+            let pattern = self.parse_pattern()?;
 
-    fn parse_additive_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_multiplicative_expr()?;
+            if let (PatternKind::Rest(_), Some(TokenKind::Assignment)) =
+                (&pattern.kind, self.peek_kind())
+            {
+                return Err(ParserError::RestDefault { span: pattern.span }.into());
+            }
 
-        while let Some(kind) = self.peek_kind() {
-            let op = match kind {
-                TokenKind::Plus => BinaryOp::Add,
-                TokenKind::Minus => BinaryOp::Sub,
-                _ => break,
+            // Parameters can have a default value, e.g. `verbose = false`
+            let default = if self.peek_kind() == Some(&TokenKind::Assignment) {
+                self.consume();
+                Some(self.parse_expr()?)
+            } else {
+                None
             };
 
-            // Consume the operator
-            self.consume();
-
-            let right = self.parse_multiplicative_expr()?;
-            let span = left.span.extend(right.span);
+            params.push(Param { pattern, default });
 
-            left = Expr {
-                kind: ExprKind::BinaryOp {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                    op,
-                },
-                span,
+            if self.peek_kind() == Some(&TokenKind::Separator) {
+                self.consume();
+            } else {
+                break;
             }
         }
 
-        Ok(left)
-    }
+        self.expect(&TokenKind::RightParen, ParserError::FnArgsEnd { span })?;
 
-    fn parse_multiplicative_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_unary_expr()?;
+        self.expect(&TokenKind::LeftBrace, ParserError::FnBlockBegin { span })?;
 
-        while let Some(kind) = self.peek_kind() {
-            let op = match kind {
-                TokenKind::Star => BinaryOp::Mul,
-                TokenKind::Slash => BinaryOp::Div,
-                _ => break,
-            };
+        let body = self.process(|token| match token {
+            TokenKind::RightBrace => Process::Break,
+            _ => Process::Push,
+        })?;
 
-            // Consume the operator
-            self.consume();
+        let end = self.expect(&TokenKind::RightBrace, ParserError::FnBlockEnd { span })?;
 
-            let right = self.parse_unary_expr()?;
-            let span = left.span.extend(right.span);
+        self.labels = labels;
 
-            left = Expr {
-                kind: ExprKind::BinaryOp {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                    op,
-                },
-                span,
+        Ok((params, body, end))
+    }
+
+    fn parse_args(&mut self, end: &TokenKind<'a>) -> Result<(Vec<Expr>, Option<usize>)> {
+        let mut args = Vec::new();
+
+        if self.peek_kind() == Some(end) {
+            return Ok((args, None));
+        }
+
+        // To keep track of the last column of the last [`Expr`]
+        let mut last;
+        loop {
+            // First argument won't be preceded by a separator
+            let arg = self.parse_arg()?;
+            last = Some(arg.span.offset() + arg.span.len());
+            args.push(arg);
+
+            // Subsequent arguments will be
+            if self.peek_kind() == Some(&TokenKind::Separator) {
+                self.consume();
+            } else {
+                break;
             }
         }
 
-        Ok(left)
+        Ok((args, last))
     }
 
-    fn parse_unary_expr(&mut self) -> Result<Expr> {
-        match self.peek_kind() {
-            Some(&TokenKind::Plus | &TokenKind::Minus | &TokenKind::Bang) => {
-                let token = self.consume();
+    /// Parses an argument of a call or an item of a list, which may be spread, e.g. `..items`.
+    fn parse_arg(&mut self) -> Result<Expr> {
+        if self.peek_kind() != Some(&TokenKind::Range) {
+            return self.parse_expr();
+        }
 
-                let op = match token.kind {
-                    TokenKind::Plus => UnaryOp::Pos,
-                    TokenKind::Minus => UnaryOp::Neg,
-                    TokenKind::Bang => UnaryOp::Not,
-                    _ => unreachable!(),
-                };
+        let span = self.consume().span;
+        let expr = self.parse_expr()?;
+        let span = span.extend(expr.span);
 
-                // We should keep parsing as many unary operators as we can
-                let right = self.parse_unary_expr()?;
-                let span = token.span.extend(right.span);
+        Ok(Expr {
+            kind: ExprKind::Spread {
+                expr: Box::new(expr),
+            },
+            span,
+        })
+    }
 
-                Ok(Expr {
-                    kind: ExprKind::UnaryOp {
-                        expr: Box::new(right),
-                        op,
-                    },
-                    span,
-                })
+    /// Parses the arguments of a call, returning the positional and named arguments separately,
+    /// along with the last column of the last argument.
+    fn parse_call_args(&mut self) -> Result<(Vec<Expr>, Vec<NamedArg>, Option<usize>)> {
+        let mut args = Vec::new();
+        let mut named: Vec<NamedArg> = Vec::new();
+        let mut last = None;
+
+        while !matches!(self.peek_kind(), Some(TokenKind::RightParen) | None) {
+            // Named arguments are of the form `ident = value`
+            if let (Some(&TokenKind::Ident(ident)), Some(TokenKind::Assignment)) =
+                (self.peek_kind(), self.peek_nth_kind(1))
+            {
+                let span = self.consume().span;
+                self.consume();
+
+                if named.iter().any(|(existing, _)| existing == ident) {
+                    return Err(ParserError::DuplicateNamedArg {
+                        ident: ident.to_string(),
+                        span,
+                    }
+                    .into());
+                }
+
+                let value = self.parse_expr()?;
+                last = Some(value.span.offset() + value.span.len());
+                named.push((ident.to_string(), value));
+            } else {
+                let arg = self.parse_arg()?;
+
+                if !named.is_empty() {
+                    return Err(ParserError::PositionalAfterNamed { span: arg.span }.into());
+                }
+
+                last = Some(arg.span.offset() + arg.span.len());
+                args.push(arg);
+            }
+
+            if self.peek_kind() == Some(&TokenKind::Separator) {
+                self.consume();
+            } else {
+                break;
             }
-            _ => self.parse_call_expr(),
         }
+
+        Ok((args, named, last))
     }
 
-    fn parse_call_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_mod_expr()?;
+    fn parse_cond(&mut self) -> Result<(Cond, SourceSpan)> {
+        // The `if` keyword has already been consumed
+        let condition = self.parse_expr()?;
+        let span = condition.span;
 
-        if self.peek_kind() == Some(&TokenKind::LeftParen) {
-            self.consume();
+        self.expect(&TokenKind::LeftBrace, ParserError::CondBlockBegin { span })?;
 
-            let (args, last) = self.parse_args(&TokenKind::RightParen)?;
-            let last = last.unwrap_or(left.span.offset());
+        let body = self.process(|token| match token {
+            TokenKind::RightBrace => Process::Break,
+            _ => Process::Push,
+        })?;
 
-            self.expect(
-                &TokenKind::RightParen,
-                ParserError::FnArgsEnd { span: left.span },
-            )?;
+        let end = self.expect(&TokenKind::RightBrace, ParserError::CondBlockEnd { span })?;
 
-            let span = (left.span.offset(), last - left.span.offset() + 1);
+        let (alternate, end) = match self.parse_alternate()? {
+            Some((alternate, end)) => (Some(Box::new(alternate)), end),
+            None => (None, end.span),
+        };
 
-            left = Expr {
-                kind: ExprKind::Call {
-                    caller: Box::new(left),
-                    args,
-                },
-                span: span.into(),
-            }
-        }
+        let cond = Cond {
+            condition,
+            body,
+            alternate,
+            span,
+        };
 
-        Ok(left)
+        Ok((cond, end))
     }
 
-    fn parse_mod_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_list_expr()?;
+    /// Parses the `else` branch of a conditional, if any, along with the span of its end.
+    fn parse_alternate(&mut self) -> Result<Option<(Alternate, SourceSpan)>> {
+        if self.peek_kind() != Some(&TokenKind::Else) {
+            return Ok(None);
+        }
+
+        // Consume the `else` keyword
+        let span = self.consume().span;
 
-        while self.peek_kind() == Some(&TokenKind::Dot) {
+        // An `else if` chains another conditional
+        if self.peek_kind() == Some(&TokenKind::Cond) {
             self.consume();
+            let (cond, end) = self.nested(Self::parse_cond)?;
+            return Ok(Some((Alternate::Cond(cond), end)));
+        }
 
-            let right = self.parse_list_expr()?;
-            let span = left.span.extend(right.span);
+        self.expect(&TokenKind::LeftBrace, ParserError::ElseBlockBegin { span })?;
+
+        let body = self.process(|token| match token {
+            TokenKind::RightBrace => Process::Break,
+            _ => Process::Push,
+        })?;
+
+        let end = self.expect(&TokenKind::RightBrace, ParserError::ElseBlockEnd { span })?;
+
+        Ok(Some((Alternate::Body { body, span }, end.span)))
+    }
+
+    fn parse_block(&mut self, span: SourceSpan) -> Result<Expr> {
+        // The opening brace has already been consumed
+        let body = self.process(|token| match token {
+            TokenKind::RightBrace => Process::Break,
+            _ => Process::Push,
+        })?;
+
+        let end = self.expect(&TokenKind::RightBrace, ParserError::BlockEnd { span })?;
+
+        Ok(Expr {
+            kind: ExprKind::Block { body },
+            span: span.extend(end.span),
+        })
+    }
+
+    fn parse_try(&mut self, span: SourceSpan) -> Result<Expr> {
+        // The `try` keyword has already been consumed
+        self.expect(&TokenKind::LeftBrace, ParserError::TryBlockBegin { span })?;
+
+        let body = self.process(|token| match token {
+            TokenKind::RightBrace => Process::Break,
+            _ => Process::Push,
+        })?;
+
+        self.expect(&TokenKind::RightBrace, ParserError::TryBlockEnd { span })?;
+
+        let catch_span = self
+            .expect(&TokenKind::Catch, ParserError::TryCatch { span })?
+            .span;
+
+        // The caught error can optionally be bound, e.g. `catch err { .. }`
+        let ident = match self.peek_kind() {
+            Some(&TokenKind::Ident(ident)) => {
+                self.consume();
+                Some(ident.to_string())
+            }
+            _ => None,
+        };
+
+        self.expect(
+            &TokenKind::LeftBrace,
+            ParserError::CatchBlockBegin { span: catch_span },
+        )?;
+
+        let catch = self.process(|token| match token {
+            TokenKind::RightBrace => Process::Break,
+            _ => Process::Push,
+        })?;
+
+        let end = self.expect(
+            &TokenKind::RightBrace,
+            ParserError::CatchBlockEnd { span: catch_span },
+        )?;
+
+        Ok(Expr {
+            kind: ExprKind::Try { body, ident, catch },
+            span: span.extend(end.span),
+        })
+    }
+
+    fn parse_match(&mut self, span: SourceSpan) -> Result<Expr> {
+        let scrutinee = self.parse_expr()?;
+
+        self.expect(
+            &TokenKind::LeftBrace,
+            ParserError::MatchBlockBegin {
+                span: scrutinee.span,
+            },
+        )?;
+
+        let mut arms = Vec::new();
+
+        while !matches!(self.peek_kind(), Some(TokenKind::RightBrace) | None) {
+            arms.push(self.parse_match_arm()?);
+
+            // Arms may optionally be separated by commas
+            if self.peek_kind() == Some(&TokenKind::Separator) {
+                self.consume();
+            }
+        }
+
+        let end = self.expect(
+            &TokenKind::RightBrace,
+            ParserError::MatchBlockEnd {
+                span: scrutinee.span,
+            },
+        )?;
+
+        Ok(Expr {
+            kind: ExprKind::Match {
+                scrutinee: Box::new(scrutinee),
+                arms,
+            },
+            span: span.extend(end.span),
+        })
+    }
+
+    fn parse_match_arm(&mut self) -> Result<MatchArm> {
+        let pattern = self.parse_pattern()?;
+
+        if let PatternKind::Rest(_) = pattern.kind {
+            return Err(ParserError::MisplacedRest { span: pattern.span }.into());
+        }
+
+        let guard = if self.peek_kind() == Some(&TokenKind::Cond) {
+            self.consume();
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+
+        let span = guard.as_ref().map_or(pattern.span, |guard| guard.span);
+        self.expect(&TokenKind::FatArrow, ParserError::MatchArmArrow { span })?;
+
+        // Arm bodies are either a single expression or a block
+        let body = if self.peek_kind() == Some(&TokenKind::LeftBrace) {
+            self.consume();
+
+            let body = self.process(|token| match token {
+                TokenKind::RightBrace => Process::Break,
+                _ => Process::Push,
+            })?;
+
+            self.expect(&TokenKind::RightBrace, ParserError::MatchArmEnd { span })?;
+
+            body
+        } else {
+            vec![Stmt::Expr(self.parse_expr()?)]
+        };
+
+        Ok(MatchArm {
+            pattern,
+            guard,
+            body,
+        })
+    }
+
+    fn parse_pattern(&mut self) -> Result<Pattern> {
+        let Some(Token { kind, span }) = self.peek().copied() else {
+            let span = (self.offset + self.source.len(), 0).into();
+            return Err(ParserError::UnexpectedEnd { span }.into());
+        };
+
+        let (kind, span) = match kind {
+            TokenKind::Ident("_") => {
+                self.consume();
+                (PatternKind::Wildcard, span)
+            }
+            TokenKind::Ident(_) if self.peek_nth_kind(1) == Some(&TokenKind::Dot) => {
+                return self.parse_variant_pattern();
+            }
+            TokenKind::Ident(ident) => {
+                self.consume();
+                (PatternKind::Binding(ident.to_string()), span)
+            }
+            TokenKind::Range => {
+                self.consume();
+                match self.peek_kind() {
+                    // `.._` is the same as `..`
+                    Some(&TokenKind::Ident(ident)) => {
+                        let ident_span = self.consume().span;
+                        let ident = (ident != "_").then(|| ident.to_string());
+                        (PatternKind::Rest(ident), span.extend(ident_span))
+                    }
+                    _ => (PatternKind::Rest(None), span),
+                }
+            }
+            TokenKind::LeftBracket => {
+                self.consume();
+
+                let items = self.parse_patterns(&TokenKind::RightBracket)?;
+
+                let end = self.expect(
+                    &TokenKind::RightBracket,
+                    ParserError::ListPatternEnd { span },
+                )?;
+
+                (PatternKind::List(items), span.extend(end.span))
+            }
+            TokenKind::Int(_)
+            | TokenKind::Float(_)
+            | TokenKind::Str(_)
+            | TokenKind::RawStr(_)
+            | TokenKind::Bool(_) => {
+                let literal = self.parse_primary_expr()?;
+                (PatternKind::Literal(literal), span)
+            }
+            // Negative numeric literals
+            TokenKind::Minus
+                if matches!(
+                    self.peek_nth_kind(1),
+                    Some(TokenKind::Int(_) | TokenKind::Float(_))
+                ) =>
+            {
+                self.consume();
+
+                let literal = self.parse_primary_expr()?;
+                let span = span.extend(literal.span);
+
+                let literal = Expr {
+                    kind: ExprKind::UnaryOp {
+                        expr: Box::new(literal),
+                        op: UnaryOp::Neg,
+                    },
+                    span,
+                };
+
+                (PatternKind::Literal(literal), span)
+            }
+            _ => return Err(ParserError::InvalidPattern { span }.into()),
+        };
+
+        Ok(Pattern { kind, span })
+    }
+
+    /// Parses patterns separated by commas, up to (but not including) `end`.
+    fn parse_patterns(&mut self, end: &TokenKind<'a>) -> Result<Vec<Pattern>> {
+        let mut patterns: Vec<Pattern> = Vec::new();
+
+        while self.peek_kind().is_some_and(|kind| kind != end) {
+            let pattern = self.nested(Self::parse_pattern)?;
+
+            // Only a single rest pattern is allowed, since otherwise the items it matches would
+            // be ambiguous
+            if let PatternKind::Rest(_) = pattern.kind {
+                if patterns
+                    .iter()
+                    .any(|pattern| matches!(pattern.kind, PatternKind::Rest(_)))
+                {
+                    return Err(ParserError::MisplacedRest { span: pattern.span }.into());
+                }
+            }
+
+            patterns.push(pattern);
+
+            if self.peek_kind() == Some(&TokenKind::Separator) {
+                self.consume();
+            } else {
+                break;
+            }
+        }
+
+        Ok(patterns)
+    }
+
+    /// Parses an enum variant pattern, e.g. `Shape.Circle(radius)`.
+    fn parse_variant_pattern(&mut self) -> Result<Pattern> {
+        let mut span = self.peek().expect("tokens should not be empty").span;
+        let mut path = Vec::new();
+
+        // The last identifier in the path is the variant
+        let variant = loop {
+            let token = self.consume();
+            span = span.extend(token.span);
+
+            let TokenKind::Ident(ident) = token.kind else {
+                return Err(ParserError::InvalidPattern { span: token.span }.into());
+            };
+
+            if self.peek_kind() != Some(&TokenKind::Dot) {
+                break ident.to_string();
+            }
+
+            self.consume();
+            path.push(ident.to_string());
+        };
+
+        let payload = if self.peek_kind() == Some(&TokenKind::LeftParen) {
+            self.consume();
+
+            let payload = self.parse_patterns(&TokenKind::RightParen)?;
+
+            if let Some(rest) = payload
+                .iter()
+                .find(|pattern| matches!(pattern.kind, PatternKind::Rest(_)))
+            {
+                return Err(ParserError::MisplacedRest { span: rest.span }.into());
+            }
+
+            let end = self.expect(
+                &TokenKind::RightParen,
+                ParserError::VariantPatternEnd { span },
+            )?;
+            span = span.extend(end.span);
+
+            Some(payload)
+        } else {
+            None
+        };
+
+        Ok(Pattern {
+            kind: PatternKind::Variant {
+                path,
+                variant,
+                payload,
+            },
+            span,
+        })
+    }
+
+    fn parse_var(&mut self) -> Result<Var> {
+        // Consume the `let` or `const` keyword
+        self.consume();
+
+        let pattern = self.parse_pattern()?;
+
+        if let PatternKind::Rest(_) = pattern.kind {
+            return Err(ParserError::MisplacedRest { span: pattern.span }.into());
+        }
+
+        self.expect(
+            &TokenKind::Assignment,
+            ParserError::VarAssignment { span: pattern.span },
+        )?;
+
+        let var = Var {
+            pattern,
+            value: Box::new(self.parse_expr()?.into()),
+        };
+
+        Ok(var)
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr> {
+        self.nested(Self::parse_assignment_expr)
+    }
+
+    fn parse_assignment_expr(&mut self) -> Result<Expr> {
+        let mut left = self.parse_pipe_expr()?;
+
+        match self.peek_kind() {
+            Some(&TokenKind::Assignment) => {
+                self.consume();
+
+                let right = self.parse_pipe_expr()?;
+                let span = left.span.extend(right.span);
+
+                left = Expr {
+                    kind: ExprKind::Assignment {
+                        assignee: Box::new(left),
+                        value: Box::new(right),
+                    },
+                    span,
+                }
+            }
+            Some(&TokenKind::CompoundAssignment(op)) => {
+                self.consume();
+
+                let right = self.parse_pipe_expr()?;
+                let span = left.span.extend(right.span);
+
+                left = Expr {
+                    kind: ExprKind::CompoundAssignment {
+                        assignee: Box::new(left),
+                        value: Box::new(right),
+                        op,
+                    },
+                    span,
+                }
+            }
+            _ => (),
+        }
+
+        Ok(left)
+    }
+
+    fn parse_pipe_expr(&mut self) -> Result<Expr> {
+        let left = self.parse_logical_or_expr()?;
+        self.parse_pipes(left)
+    }
+
+    /// Parses any pipes following `left`.
+    fn parse_pipes(&mut self, mut left: Expr) -> Result<Expr> {
+        let links = self.links;
+
+        while let Some(&TokenKind::Pipe) = self.peek_kind() {
+            // Consume the operator
+            self.consume();
+            self.link()?;
+
+            let right = self.parse_logical_or_expr()?;
+            let span = left.span.extend(right.span);
+
+            left = Expr {
+                kind: ExprKind::Pipe {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+                span,
+            }
+        }
+
+        self.links = links;
+
+        Ok(left)
+    }
+
+    fn parse_logical_or_expr(&mut self) -> Result<Expr> {
+        let links = self.links;
+        let mut left = self.parse_logical_and_expr()?;
+
+        while let Some(&TokenKind::Or) = self.peek_kind() {
+            // Consume the operator
+            self.consume();
+            self.link()?;
+
+            let right = self.parse_logical_and_expr()?;
+            let span = left.span.extend(right.span);
+
+            left = Expr {
+                kind: ExprKind::LogicOp {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    op: LogicOp::Or,
+                },
+                span,
+            }
+        }
+
+        self.links = links;
+
+        Ok(left)
+    }
+
+    fn parse_logical_and_expr(&mut self) -> Result<Expr> {
+        let links = self.links;
+        let mut left = self.parse_rel_expr()?;
+
+        while let Some(&TokenKind::And) = self.peek_kind() {
+            // Consume the operator
+            self.consume();
+            self.link()?;
+
+            let right = self.parse_rel_expr()?;
+            let span = left.span.extend(right.span);
+
+            left = Expr {
+                kind: ExprKind::LogicOp {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    op: LogicOp::And,
+                },
+                span,
+            }
+        }
+
+        self.links = links;
+
+        Ok(left)
+    }
+
+    fn parse_rel_expr(&mut self) -> Result<Expr> {
+        let mut left = self.parse_range_expr()?;
+
+        if let Some(&TokenKind::RelOp(op)) = self.peek_kind() {
+            // Consume the operator
+            self.consume();
+
+            let right = self.parse_range_expr()?;
+            let span = left.span.extend(right.span);
+
+            left = Expr {
+                kind: ExprKind::RelOp {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    op,
+                },
+                span,
+            }
+        }
+
+        Ok(left)
+    }
+
+    fn parse_range_expr(&mut self) -> Result<Expr> {
+        let mut left = self.parse_bitwise_or_expr()?;
+
+        let inclusive = match self.peek_kind() {
+            Some(TokenKind::Range) => false,
+            Some(TokenKind::RangeInclusive) => true,
+            _ => return Ok(left),
+        };
+
+        // Consume the operator
+        self.consume();
+
+        let right = self.parse_bitwise_or_expr()?;
+        let span = left.span.extend(right.span);
+
+        left = Expr {
+            kind: ExprKind::Range {
+                start: Box::new(left),
+                end: Box::new(right),
+                inclusive,
+            },
+            span,
+        };
+
+        Ok(left)
+    }
+
+    fn parse_bitwise_or_expr(&mut self) -> Result<Expr> {
+        self.parse_binary_expr(Self::parse_bitwise_xor_expr, |kind| match kind {
+            TokenKind::Bar => Some(BinaryOp::BitOr),
+            _ => None,
+        })
+    }
+
+    fn parse_bitwise_xor_expr(&mut self) -> Result<Expr> {
+        self.parse_binary_expr(Self::parse_bitwise_and_expr, |kind| match kind {
+            TokenKind::Caret => Some(BinaryOp::BitXor),
+            _ => None,
+        })
+    }
+
+    fn parse_bitwise_and_expr(&mut self) -> Result<Expr> {
+        self.parse_binary_expr(Self::parse_shift_expr, |kind| match kind {
+            TokenKind::Ampersand => Some(BinaryOp::BitAnd),
+            _ => None,
+        })
+    }
+
+    fn parse_shift_expr(&mut self) -> Result<Expr> {
+        self.parse_binary_expr(Self::parse_additive_expr, |kind| match kind {
+            TokenKind::LeftShift => Some(BinaryOp::Shl),
+            TokenKind::RightShift => Some(BinaryOp::Shr),
+            _ => None,
+        })
+    }
+
+    fn parse_additive_expr(&mut self) -> Result<Expr> {
+        self.parse_binary_expr(Self::parse_multiplicative_expr, |kind| match kind {
+            TokenKind::Plus => Some(BinaryOp::Add),
+            TokenKind::Minus => Some(BinaryOp::Sub),
+            _ => None,
+        })
+    }
+
+    fn parse_multiplicative_expr(&mut self) -> Result<Expr> {
+        self.parse_binary_expr(Self::parse_unary_expr, |kind| match kind {
+            TokenKind::Star => Some(BinaryOp::Mul),
+            TokenKind::Slash => Some(BinaryOp::Div),
+            TokenKind::Percent => Some(BinaryOp::Rem),
+            _ => None,
+        })
+    }
+
+    /// Parses a left-associative binary expression, where `operand` parses the operands at the
+    /// next level of precedence and `op` maps tokens to the operators at this level.
+    fn parse_binary_expr(
+        &mut self,
+        operand: fn(&mut Self) -> Result<Expr>,
+        op: fn(&TokenKind) -> Option<BinaryOp>,
+    ) -> Result<Expr> {
+        let links = self.links;
+        let mut left = operand(self)?;
+
+        while let Some(op) = self.peek_kind().and_then(op) {
+            // Consume the operator
+            self.consume();
+            self.link()?;
+
+            let right = operand(self)?;
+            let span = left.span.extend(right.span);
+
+            left = Expr {
+                kind: ExprKind::BinaryOp {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    op,
+                },
+                span,
+            }
+        }
+
+        self.links = links;
+
+        Ok(left)
+    }
+
+    fn parse_unary_expr(&mut self) -> Result<Expr> {
+        match self.peek_kind() {
+            Some(&TokenKind::Plus | &TokenKind::Minus | &TokenKind::Bang) => {
+                let token = self.consume();
+
+                let op = match token.kind {
+                    TokenKind::Plus => UnaryOp::Pos,
+                    TokenKind::Minus => UnaryOp::Neg,
+                    TokenKind::Bang => UnaryOp::Not,
+                    _ => unreachable!(),
+                };
+
+                // We should keep parsing as many unary operators as we can
+                let right = self.nested(Self::parse_unary_expr)?;
+                let span = token.span.extend(right.span);
+
+                Ok(Expr {
+                    kind: ExprKind::UnaryOp {
+                        expr: Box::new(right),
+                        op,
+                    },
+                    span,
+                })
+            }
+            _ => self.parse_exponent_expr(),
+        }
+    }
+
+    fn parse_exponent_expr(&mut self) -> Result<Expr> {
+        let left = self.parse_call_expr()?;
+
+        if self.peek_kind() != Some(&TokenKind::DoubleStar) {
+            return Ok(left);
+        }
+
+        // Consume the operator
+        self.consume();
+
+        // Exponents are right-associative, and bind tighter than unary operators on their left,
+        // e.g. `-2 ** -2` is `-(2 ** (-2))`
+        let right = self.nested(Self::parse_unary_expr)?;
+        let span = left.span.extend(right.span);
+
+        Ok(Expr {
+            kind: ExprKind::BinaryOp {
+                left: Box::new(left),
+                right: Box::new(right),
+                op: BinaryOp::Pow,
+            },
+            span,
+        })
+    }
+
+    fn parse_call_expr(&mut self) -> Result<Expr> {
+        let links = self.links;
+        let mut left = self.parse_list_expr()?;
+
+        // Calls, indexes and members can be chained, e.g. `foo()()`, `foo[0]()` or `foo()[0].bar`
+        loop {
+            left = match self.peek_kind() {
+                Some(TokenKind::LeftParen) => {
+                    self.link()?;
+                    self.parse_call(left)?
+                }
+                Some(TokenKind::LeftBracket) if self.is_same_line(left.span) => {
+                    self.link()?;
+                    self.parse_index_expr(left)?
+                }
+                Some(TokenKind::Dot) => {
+                    self.link()?;
+                    self.parse_mod_expr(left)?
+                }
+                _ => break,
+            };
+        }
+
+        self.links = links;
+
+        Ok(left)
+    }
+
+    fn parse_call(&mut self, caller: Expr) -> Result<Expr> {
+        // Consume the left paren
+        self.consume();
+
+        let (args, named, last) = self.parse_call_args()?;
+        let last = last.unwrap_or(caller.span.offset());
+
+        self.expect(
+            &TokenKind::RightParen,
+            ParserError::FnArgsEnd { span: caller.span },
+        )?;
+
+        let span = (caller.span.offset(), last - caller.span.offset() + 1);
+
+        Ok(Expr {
+            kind: ExprKind::Call {
+                caller: Box::new(caller),
+                args,
+                named,
+            },
+            span: span.into(),
+        })
+    }
+
+    fn parse_index_expr(&mut self, expr: Expr) -> Result<Expr> {
+        // Consume the left bracket
+        self.consume();
+
+        let index = self.parse_expr()?;
+
+        let end = self.expect(
+            &TokenKind::RightBracket,
+            ParserError::IndexEnd { span: index.span },
+        )?;
+
+        let span = expr.span.extend(end.span);
+
+        Ok(Expr {
+            kind: ExprKind::Index {
+                expr: Box::new(expr),
+                index: Box::new(index),
+            },
+            span,
+        })
+    }
+
+    /// Checks whether the next token is on the same line as the end of `span`.
+    ///
+    /// Used to distinguish postfix operators from expressions on the next line, e.g. `foo[0]` from
+    /// `foo` followed by a list.
+    fn is_same_line(&self, span: SourceSpan) -> bool {
+        let Some(next) = self.peek() else {
+            return false;
+        };
+
+        let start = span.offset() + span.len() - self.offset;
+        let end = next.span.offset() - self.offset;
+
+        !self.source[start..end].contains('\n')
+    }
+
+    fn parse_mod_expr(&mut self, module: Expr) -> Result<Expr> {
+        // Consume the dot
+        self.consume();
+
+        let item = self.parse_list_expr()?;
+        let span = module.span.extend(item.span);
+
+        Ok(Expr {
+            kind: ExprKind::Mod {
+                module: Box::new(module),
+                item: Box::new(item),
+            },
+            span,
+        })
+    }
+
+    fn parse_list_expr(&mut self) -> Result<Expr> {
+        if self.peek_kind() != Some(&TokenKind::LeftBracket) {
+            return self.parse_map_expr();
+        }
+
+        let left = self.consume();
+
+        let (items, last) = self.parse_args(&TokenKind::RightBracket)?;
+        let last = last.unwrap_or(left.span.offset());
+
+        let span = left.span.extend(last.into());
+
+        self.expect(&TokenKind::RightBracket, ParserError::ListItemsEnd { span })?;
+
+        Ok(Expr {
+            kind: ExprKind::List { items },
+            // `span` doesn't include the end `RightBracket`
+            span: (span.offset(), span.len() + 1).into(),
+        })
+    }
+
+    fn parse_map_expr(&mut self) -> Result<Expr> {
+        if self.peek_kind() != Some(&TokenKind::LeftMapBrace) {
+            return self.parse_primary_expr();
+        }
+
+        let left = self.consume();
+        let mut span = left.span;
+
+        let mut entries = Vec::new();
+
+        if self.peek_kind() != Some(&TokenKind::RightBrace) {
+            loop {
+                // First entry won't be preceded by a separator
+                let key = self.parse_expr()?;
+                self.expect(
+                    &TokenKind::Colon,
+                    ParserError::MapEntryColon { span: key.span },
+                )?;
+                let value = self.parse_expr()?;
+                span = span.extend(value.span);
+                entries.push((key, value));
+
+                // Subsequent entries will be
+                if self.peek_kind() == Some(&TokenKind::Separator) {
+                    self.consume();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        self.expect(&TokenKind::RightBrace, ParserError::MapEntriesEnd { span })?;
+
+        Ok(Expr {
+            kind: ExprKind::Map { entries },
+            // `span` doesn't include the end `RightBrace`
+            span: (span.offset(), span.len() + 1).into(),
+        })
+    }
+
+    fn parse_primary_expr(&mut self) -> Result<Expr> {
+        let Some(Token { kind, span }) = self.tokens.pop_front() else {
+            let span = (self.offset + self.source.len(), 0).into();
+            return Err(ParserError::UnexpectedEnd { span }.into());
+        };
+
+        let expr = match kind {
+            TokenKind::Ident(value) => Expr {
+                kind: ExprKind::Ident(value.to_string()),
+                span,
+            },
+            TokenKind::Bool(value) => {
+                let value = match value {
+                    "true" => true,
+                    "false" => false,
+                    _ => unreachable!("`Bool` token should have value `true` or `false`"),
+                };
+                Expr {
+                    kind: ExprKind::Bool(value),
+                    span,
+                }
+            }
+            TokenKind::Int(value) => Expr {
+                kind: ExprKind::Int(
+                    value
+                        .parse::<i32>()
+                        .expect("`Int` token should be parsed as an `i32`"),
+                ),
+                span,
+            },
+            TokenKind::Float(value) => Expr {
+                kind: ExprKind::Float(
+                    value
+                        .parse::<f64>()
+                        .expect("`Float` token should be parsed as an `f64`"),
+                ),
+                span,
+            },
+            TokenKind::Str(value) => self.parse_str(value, span)?,
+            TokenKind::RawStr(value) => Expr {
+                kind: ExprKind::Str(value.to_string()),
+                span,
+            },
+            TokenKind::LeftParen => {
+                let expr = self.parse_expr()?;
+                // Consume closing parenthesis
+                self.consume();
+                expr
+            }
+            TokenKind::Func => self.parse_lambda(span)?,
+            TokenKind::Cond => {
+                let (cond, end) = self.parse_cond()?;
+                Expr {
+                    kind: ExprKind::Cond(Box::new(cond)),
+                    span: span.extend(end),
+                }
+            }
+            TokenKind::LeftBrace => self.parse_block(span)?,
+            TokenKind::Match => self.parse_match(span)?,
+            TokenKind::Try => self.parse_try(span)?,
+            TokenKind::Throw => {
+                let value = self.parse_expr()?;
+                let span = span.extend(value.span);
+                Expr {
+                    kind: ExprKind::Throw {
+                        value: Box::new(value),
+                    },
+                    span,
+                }
+            }
+            TokenKind::Return => {
+                let (value, len) = if let Some(TokenKind::RightBrace) = self.peek_kind() {
+                    (None, 0)
+                } else {
+                    let expr = self.parse_expr()?;
+                    let len = expr.span.len();
+                    (Some(Box::new(expr)), len)
+                };
+                let span = (span.offset(), len + span.len());
+                Expr {
+                    kind: ExprKind::Return { value },
+                    span: span.into(),
+                }
+            }
+            TokenKind::Loop | TokenKind::While | TokenKind::For => {
+                self.parse_loop_expr(kind, span, None, span)?
+            }
+            TokenKind::Label(label) => {
+                self.expect(&TokenKind::Colon, ParserError::LabelColon { span })?;
+
+                let Some(Token {
+                    kind,
+                    span: loop_span,
+                }) = self.tokens.pop_front()
+                else {
+                    return Err(ParserError::LabelLoop { span }.into());
+                };
+
+                if !matches!(kind, TokenKind::Loop | TokenKind::While | TokenKind::For) {
+                    return Err(ParserError::LabelLoop { span }.into());
+                }
+
+                self.parse_loop_expr(kind, loop_span, Some(label.to_string()), span)?
+            }
+            TokenKind::Continue => {
+                let (label, label_span) = self.parse_jump_label()?;
+                Expr {
+                    kind: ExprKind::Continue { label },
+                    span: label_span.map_or(span, |label_span| span.extend(label_span)),
+                }
+            }
+            TokenKind::Break => {
+                let (label, label_span) = self.parse_jump_label()?;
+                let mut end = label_span.unwrap_or(span);
+
+                // A value can follow on the same line, unless the enclosing block or arm ends
+                let value = match self.peek_kind() {
+                    Some(TokenKind::RightBrace | TokenKind::RightParen | TokenKind::Separator)
+                    | None => None,
+                    _ if !self.is_same_line(end) => None,
+                    _ => {
+                        let value = self.parse_expr()?;
+                        end = value.span;
+                        Some(Box::new(value))
+                    }
+                };
+
+                Expr {
+                    kind: ExprKind::Break { label, value },
+                    span: span.extend(end),
+                }
+            }
+            _ => return Err(ParserError::Unsupported { span }.into()),
+        };
+
+        Ok(expr)
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Parses the contents of a string literal, splitting out any interpolated expressions.
+    fn parse_str(&self, value: &'a str, span: SourceSpan) -> Result<Expr> {
+        // The contents of the string begin after the opening quote
+        let offset = span.offset() + 1;
+
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut literal_start = 0;
+        let mut idx = 0;
+
+        while let Some(ch) = value[idx..].chars().next() {
+            match ch {
+                '\\' => {
+                    let (ch, len) = read_escape(&value[idx + 1..])
+                        .expect("escape sequences should be validated");
+                    literal.push(ch);
+                    idx += 1 + len;
+                    continue;
+                }
+                // Escaped braces, i.e. `{{` and `}}`
+                '{' | '}' if value[idx + 1..].starts_with(ch) => {
+                    literal.push(ch);
+                    idx += 2;
+                    continue;
+                }
+                '{' => {
+                    let len = find_interpolation_end(&value[idx + 1..])
+                        .expect("interpolated expressions should be terminated");
+
+                    if !literal.is_empty() {
+                        parts.push(Expr {
+                            kind: ExprKind::Str(std::mem::take(&mut literal)),
+                            span: (offset + literal_start, idx - literal_start).into(),
+                        });
+                    }
+
+                    let source = &value[idx + 1..idx + 1 + len];
+                    parts.push(self.parse_interpolation(source, offset + idx + 1)?);
+
+                    // Skip past the expression and both braces
+                    idx += len + 2;
+                    literal_start = idx;
+                    continue;
+                }
+                _ => literal.push(ch),
+            }
+            idx += ch.len_utf8();
+        }
+
+        if parts.is_empty() {
+            return Ok(Expr {
+                kind: ExprKind::Str(literal),
+                span,
+            });
+        }
+
+        if !literal.is_empty() {
+            parts.push(Expr {
+                kind: ExprKind::Str(literal),
+                span: (offset + literal_start, idx - literal_start).into(),
+            });
+        }
+
+        Ok(Expr {
+            kind: ExprKind::Interp { parts },
+            span,
+        })
+    }
+
+    /// Parses an interpolated expression found at `offset` in the program.
+    fn parse_interpolation(&self, source: &'a str, offset: usize) -> Result<Expr> {
+        let tokens = Lexer::with_offset(source, offset).tokenize()?;
+
+        if tokens.is_empty() {
+            // Include the surrounding braces in the span
+            let span = (offset - 1, source.len() + 2).into();
+            return Err(ParserError::EmptyInterpolation { span }.into());
+        }
+
+        let mut parser = Parser {
+            tokens: tokens.into(),
+            source,
+            offset,
+            labels: self.labels.clone(),
+            depth: self.depth,
+            links: self.links,
+        };
+
+        let expr = parser.parse_expr()?;
+
+        // The whole interpolation should be a single expression
+        if let Some(token) = parser.peek() {
+            return Err(ParserError::Unsupported { span: token.span }.into());
+        }
+
+        Ok(expr)
+    }
+}
+
+pub trait SourceSpanExt {
+    fn extend(&self, span: SourceSpan) -> SourceSpan;
+}
+
+impl SourceSpanExt for SourceSpan {
+    fn extend(&self, span: Self) -> Self {
+        (self.offset(), span.offset() - self.offset() + span.len()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::RelOp;
+
+    use super::*;
+
+    trait VecExt {
+        fn to_program(self) -> Stmt;
+    }
+
+    impl VecExt for Vec<Stmt> {
+        fn to_program(self) -> Stmt {
+            Stmt::Program { body: self }
+        }
+    }
+
+    #[test]
+    fn parse_use() {
+        let src = "use foo/bar";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Use(Use {
+                path: "foo/bar".to_string(),
+                span: (4, 7).into()
+            })]
+            .to_program()
+        )
+    }
+
+    #[test]
+    fn parse_loop() {
+        let src = "loop { break }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Loop(Box::new(Loop {
+                    label: None,
+                    body: vec![Stmt::Expr(Expr {
+                        kind: ExprKind::Break {
+                            label: None,
+                            value: None
+                        },
+                        span: (7, 5).into()
+                    })],
+                    span: (0, 4).into()
+                })),
+                span: (0, 14).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_while() {
+        let src = "while foo { break }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::While(Box::new(While {
+                    label: None,
+                    condition: Expr {
+                        kind: ExprKind::Ident("foo".to_string()),
+                        span: (6, 3).into()
+                    },
+                    body: vec![Stmt::Expr(Expr {
+                        kind: ExprKind::Break {
+                            label: None,
+                            value: None
+                        },
+                        span: (12, 5).into()
+                    })],
+                    span: (0, 5).into()
+                })),
+                span: (0, 19).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_for() {
+        let src = "for foo in bar { continue }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::For(Box::new(For {
+                    label: None,
+                    ident: "foo".to_string(),
+                    iterable: Expr {
+                        kind: ExprKind::Ident("bar".to_string()),
+                        span: (11, 3).into()
+                    },
+                    body: vec![Stmt::Expr(Expr {
+                        kind: ExprKind::Continue { label: None },
+                        span: (17, 8).into()
+                    })],
+                    span: (0, 3).into()
+                })),
+                span: (0, 27).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_labeled_loop() {
+        let src = "'outer: loop { break 'outer 1 }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Loop(Box::new(Loop {
+                    label: Some("outer".to_string()),
+                    body: vec![Stmt::Expr(Expr {
+                        kind: ExprKind::Break {
+                            label: Some("outer".to_string()),
+                            value: Some(Box::new(Expr {
+                                kind: ExprKind::Int(1),
+                                span: (28, 1).into()
+                            }))
+                        },
+                        span: (15, 14).into()
+                    })],
+                    span: (8, 4).into()
+                })),
+                span: (0, 31).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_label_in_func() {
+        let src = "'outer: loop { fn() { break 'outer } }";
+        let error = Parser::new(src)
+            .produce_ast()
+            .expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<ParserError>(),
+            Some(ParserError::UndeclaredLabel { label, .. }) if label == "outer"
+        ));
+    }
+
+    #[test]
+    fn parse_func() {
+        let src = "fn foo(bar) { return }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Func(Func {
+                ident: "foo".to_string(),
+                params: vec![Param {
+                    pattern: Pattern {
+                        kind: PatternKind::Binding("bar".to_string()),
+                        span: (7, 3).into()
+                    },
+                    default: None
+                }],
+                body: vec![Stmt::Expr(Expr {
+                    kind: ExprKind::Return { value: None },
+                    span: (14, 6).into()
+                })],
+                span: (3, 3).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_cond() {
+        let src = "if 1 < 2 { true }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Cond(Box::new(Cond {
+                    condition: Expr {
+                        kind: ExprKind::RelOp {
+                            left: Box::new(Expr {
+                                kind: ExprKind::Int(1),
+                                span: (3, 1).into()
+                            }),
+                            right: Box::new(Expr {
+                                kind: ExprKind::Int(2),
+                                span: (7, 1).into()
+                            }),
+                            op: RelOp::Less
+                        },
+                        span: (3, 5).into()
+                    },
+                    body: vec![Stmt::Expr(Expr {
+                        kind: ExprKind::Bool(true),
+                        span: (11, 4).into()
+                    })],
+                    alternate: None,
+                    span: (3, 5).into()
+                })),
+                span: (0, 17).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_cond_else() {
+        let src = "if a { 0 } else if b { 1 } else { 2 }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Cond(Box::new(Cond {
+                    condition: Expr {
+                        kind: ExprKind::Ident("a".to_string()),
+                        span: (3, 1).into()
+                    },
+                    body: vec![Stmt::Expr(Expr {
+                        kind: ExprKind::Int(0),
+                        span: (7, 1).into()
+                    })],
+                    alternate: Some(Box::new(Alternate::Cond(Cond {
+                        condition: Expr {
+                            kind: ExprKind::Ident("b".to_string()),
+                            span: (19, 1).into()
+                        },
+                        body: vec![Stmt::Expr(Expr {
+                            kind: ExprKind::Int(1),
+                            span: (23, 1).into()
+                        })],
+                        alternate: Some(Box::new(Alternate::Body {
+                            body: vec![Stmt::Expr(Expr {
+                                kind: ExprKind::Int(2),
+                                span: (34, 1).into()
+                            })],
+                            span: (27, 4).into()
+                        })),
+                        span: (19, 1).into()
+                    }))),
+                    span: (3, 1).into()
+                })),
+                span: (0, 37).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_var() {
+        let src = "let foo = 0";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Var(Var {
+                pattern: Pattern {
+                    kind: PatternKind::Binding("foo".to_string()),
+                    span: (4, 3).into()
+                },
+                value: Box::new(Stmt::Expr(Expr {
+                    kind: ExprKind::Int(0),
+                    span: (10, 1).into()
+                })),
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_var_destructure() {
+        let src = "let [foo, ..bar] = baz";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Var(Var {
+                pattern: Pattern {
+                    kind: PatternKind::List(vec![
+                        Pattern {
+                            kind: PatternKind::Binding("foo".to_string()),
+                            span: (5, 3).into()
+                        },
+                        Pattern {
+                            kind: PatternKind::Rest(Some("bar".to_string())),
+                            span: (10, 5).into()
+                        }
+                    ]),
+                    span: (4, 12).into()
+                },
+                value: Box::new(Stmt::Expr(Expr {
+                    kind: ExprKind::Ident("baz".to_string()),
+                    span: (19, 3).into()
+                })),
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_const() {
+        let src = "const foo = 0";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Const(Var {
+                pattern: Pattern {
+                    kind: PatternKind::Binding("foo".to_string()),
+                    span: (6, 3).into()
+                },
+                value: Box::new(Stmt::Expr(Expr {
+                    kind: ExprKind::Int(0),
+                    span: (12, 1).into()
+                })),
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_map() {
+        let src = r#"#{"foo": 0}"#;
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Map {
+                    entries: vec![(
+                        Expr {
+                            kind: ExprKind::Str("foo".to_string()),
+                            span: (2, 5).into()
+                        },
+                        Expr {
+                            kind: ExprKind::Int(0),
+                            span: (9, 1).into()
+                        }
+                    )]
+                },
+                span: (0, 11).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_lambda() {
+        let src = "fn(foo) { foo }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Lambda {
+                    params: vec![Param {
+                        pattern: Pattern {
+                            kind: PatternKind::Binding("foo".to_string()),
+                            span: (3, 3).into()
+                        },
+                        default: None
+                    }],
+                    body: vec![Stmt::Expr(Expr {
+                        kind: ExprKind::Ident("foo".to_string()),
+                        span: (10, 3).into()
+                    })],
+                },
+                span: (0, 15).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_range() {
+        let src = "0..=1 + 1";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Range {
+                    start: Box::new(Expr {
+                        kind: ExprKind::Int(0),
+                        span: (0, 1).into()
+                    }),
+                    end: Box::new(Expr {
+                        kind: ExprKind::BinaryOp {
+                            left: Box::new(Expr {
+                                kind: ExprKind::Int(1),
+                                span: (4, 1).into()
+                            }),
+                            right: Box::new(Expr {
+                                kind: ExprKind::Int(1),
+                                span: (8, 1).into()
+                            }),
+                            op: BinaryOp::Add
+                        },
+                        span: (4, 5).into()
+                    }),
+                    inclusive: true
+                },
+                span: (0, 9).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_interpolation() {
+        let src = r#""foo {bar}{{""#;
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
 
-            left = Expr {
-                kind: ExprKind::Mod {
-                    module: Box::new(left),
-                    item: Box::new(right),
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Interp {
+                    parts: vec![
+                        Expr {
+                            kind: ExprKind::Str("foo ".to_string()),
+                            span: (1, 4).into()
+                        },
+                        Expr {
+                            kind: ExprKind::Ident("bar".to_string()),
+                            span: (6, 3).into()
+                        },
+                        Expr {
+                            kind: ExprKind::Str("{".to_string()),
+                            span: (10, 2).into()
+                        },
+                    ]
                 },
-                span,
-            }
-        }
-
-        Ok(left)
+                span: (0, 13).into()
+            })]
+            .to_program()
+        );
     }
 
-    fn parse_list_expr(&mut self) -> Result<Expr> {
-        if self.peek_kind() != Some(&TokenKind::LeftBracket) {
-            return self.parse_primary_expr();
-        }
+    #[test]
+    fn parse_exponent() {
+        let src = "-2 ** 3 % 2";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
 
-        let left = self.consume();
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::BinaryOp {
+                    left: Box::new(Expr {
+                        kind: ExprKind::UnaryOp {
+                            expr: Box::new(Expr {
+                                kind: ExprKind::BinaryOp {
+                                    left: Box::new(Expr {
+                                        kind: ExprKind::Int(2),
+                                        span: (1, 1).into()
+                                    }),
+                                    right: Box::new(Expr {
+                                        kind: ExprKind::Int(3),
+                                        span: (6, 1).into()
+                                    }),
+                                    op: BinaryOp::Pow
+                                },
+                                span: (1, 6).into()
+                            }),
+                            op: UnaryOp::Neg
+                        },
+                        span: (0, 7).into()
+                    }),
+                    right: Box::new(Expr {
+                        kind: ExprKind::Int(2),
+                        span: (10, 1).into()
+                    }),
+                    op: BinaryOp::Rem
+                },
+                span: (0, 11).into()
+            })]
+            .to_program()
+        );
+    }
 
-        let (items, last) = self.parse_args(&TokenKind::RightBracket)?;
-        let last = last.unwrap_or(left.span.offset());
+    #[test]
+    fn parse_compound_assignment() {
+        let src = "foo += 1";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
 
-        let span = left.span.extend(last.into());
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::CompoundAssignment {
+                    assignee: Box::new(Expr {
+                        kind: ExprKind::Ident("foo".to_string()),
+                        span: (0, 3).into(),
+                    }),
+                    value: Box::new(Expr {
+                        kind: ExprKind::Int(1),
+                        span: (7, 1).into()
+                    }),
+                    op: BinaryOp::Add
+                },
+                span: (0, 8).into()
+            })]
+            .to_program()
+        );
+    }
 
-        self.expect(&TokenKind::RightBracket, ParserError::ListItemsEnd { span })?;
+    #[test]
+    fn parse_index() {
+        let src = "foo[0]\n[1]";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
 
-        Ok(Expr {
-            kind: ExprKind::List { items },
-            // `span` doesn't include the end `RightBracket`
-            span: (span.offset(), span.len() + 1).into(),
-        })
+        assert_eq!(
+            ast,
+            vec![
+                Stmt::Expr(Expr {
+                    kind: ExprKind::Index {
+                        expr: Box::new(Expr {
+                            kind: ExprKind::Ident("foo".to_string()),
+                            span: (0, 3).into()
+                        }),
+                        index: Box::new(Expr {
+                            kind: ExprKind::Int(0),
+                            span: (4, 1).into()
+                        })
+                    },
+                    span: (0, 6).into()
+                }),
+                // Brackets on the following line begin a new expression
+                Stmt::Expr(Expr {
+                    kind: ExprKind::List {
+                        items: vec![Expr {
+                            kind: ExprKind::Int(1),
+                            span: (8, 1).into()
+                        }]
+                    },
+                    span: (7, 3).into()
+                })
+            ]
+            .to_program()
+        );
     }
 
-    fn parse_primary_expr(&mut self) -> Result<Expr> {
-        let Token { kind, span } = self.consume();
-
-        let expr = match kind {
-            TokenKind::Ident(value) => Expr {
-                kind: ExprKind::Ident(value.to_string()),
-                span,
-            },
-            TokenKind::Bool(value) => {
-                let value = match value {
-                    "true" => true,
-                    "false" => false,
-                    _ => unreachable!("`Bool` token should have value `true` or `false`"),
-                };
-                Expr {
-                    kind: ExprKind::Bool(value),
-                    span,
-                }
-            }
-            TokenKind::Int(value) => Expr {
-                kind: ExprKind::Int(
-                    value
-                        .parse::<i32>()
-                        .expect("`Int` token should be parsed as an `i32`"),
-                ),
-                span,
-            },
-            TokenKind::Str(value) => Expr {
-                kind: ExprKind::Str(value.to_string()),
-                span,
-            },
-            TokenKind::LeftParen => {
-                let expr = self.parse_expr()?;
-                // Consume closing parenthesis
-                self.consume();
-                expr
-            }
-            TokenKind::Return => {
-                let (value, len) = if let Some(TokenKind::RightBrace) = self.peek_kind() {
-                    (None, 0)
-                } else {
-                    let expr = self.parse_expr()?;
-                    let len = expr.span.len();
-                    (Some(Box::new(expr)), len)
-                };
-                let span = (span.offset(), len + span.len());
-                Expr {
-                    kind: ExprKind::Return { value },
-                    span: span.into(),
-                }
-            }
-            TokenKind::Continue => Expr {
-                kind: ExprKind::Continue,
-                span,
-            },
-            TokenKind::Break => Expr {
-                kind: ExprKind::Break,
-                span,
-            },
-            _ => return Err(ParserError::Unsupported { span }.into()),
-        };
+    #[test]
+    fn parse_match() {
+        let src = "match foo { [_, ..rest] if true => rest, 1 => 2 }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
 
-        Ok(expr)
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Match {
+                    scrutinee: Box::new(Expr {
+                        kind: ExprKind::Ident("foo".to_string()),
+                        span: (6, 3).into()
+                    }),
+                    arms: vec![
+                        MatchArm {
+                            pattern: Pattern {
+                                kind: PatternKind::List(vec![
+                                    Pattern {
+                                        kind: PatternKind::Wildcard,
+                                        span: (13, 1).into()
+                                    },
+                                    Pattern {
+                                        kind: PatternKind::Rest(Some("rest".to_string())),
+                                        span: (16, 6).into()
+                                    }
+                                ]),
+                                span: (12, 11).into()
+                            },
+                            guard: Some(Expr {
+                                kind: ExprKind::Bool(true),
+                                span: (27, 4).into()
+                            }),
+                            body: vec![Stmt::Expr(Expr {
+                                kind: ExprKind::Ident("rest".to_string()),
+                                span: (35, 4).into()
+                            })]
+                        },
+                        MatchArm {
+                            pattern: Pattern {
+                                kind: PatternKind::Literal(Expr {
+                                    kind: ExprKind::Int(1),
+                                    span: (41, 1).into()
+                                }),
+                                span: (41, 1).into()
+                            },
+                            guard: None,
+                            body: vec![Stmt::Expr(Expr {
+                                kind: ExprKind::Int(2),
+                                span: (46, 1).into()
+                            })]
+                        }
+                    ]
+                },
+                span: (0, 49).into()
+            })]
+            .to_program()
+        );
     }
-}
 
-pub trait SourceSpanExt {
-    fn extend(&self, span: SourceSpan) -> SourceSpan;
-}
+    #[test]
+    fn parse_struct() {
+        let src = "struct Point { x, y }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
 
-impl SourceSpanExt for SourceSpan {
-    fn extend(&self, span: Self) -> Self {
-        (self.offset(), span.offset() - self.offset() + span.len()).into()
+        assert_eq!(
+            ast,
+            vec![Stmt::Struct(Struct {
+                ident: "Point".to_string(),
+                fields: vec!["x".to_string(), "y".to_string()],
+                span: (7, 5).into()
+            })]
+            .to_program()
+        );
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::lexer::RelOp;
 
-    use super::*;
+    #[test]
+    fn parse_member_chain() {
+        let src = "foo(1)[0].bar";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
 
-    trait VecExt {
-        fn to_program(self) -> Stmt;
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Mod {
+                    module: Box::new(Expr {
+                        kind: ExprKind::Index {
+                            expr: Box::new(Expr {
+                                kind: ExprKind::Call {
+                                    caller: Box::new(Expr {
+                                        kind: ExprKind::Ident("foo".to_string()),
+                                        span: (0, 3).into()
+                                    }),
+                                    args: vec![Expr {
+                                        kind: ExprKind::Int(1),
+                                        span: (4, 1).into()
+                                    }],
+                                    named: vec![]
+                                },
+                                span: (0, 6).into()
+                            }),
+                            index: Box::new(Expr {
+                                kind: ExprKind::Int(0),
+                                span: (7, 1).into()
+                            })
+                        },
+                        span: (0, 9).into()
+                    }),
+                    item: Box::new(Expr {
+                        kind: ExprKind::Ident("bar".to_string()),
+                        span: (10, 3).into()
+                    })
+                },
+                span: (0, 13).into()
+            })]
+            .to_program()
+        );
     }
 
-    impl VecExt for Vec<Stmt> {
-        fn to_program(self) -> Stmt {
-            Stmt::Program { body: self }
-        }
+    #[test]
+    fn parse_enum() {
+        let src = "enum Shape { Circle(radius), Empty }";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Enum(Enum {
+                ident: "Shape".to_string(),
+                variants: vec![
+                    ("Circle".to_string(), vec!["radius".to_string()]),
+                    ("Empty".to_string(), vec![])
+                ],
+                span: (5, 5).into()
+            })]
+            .to_program()
+        );
     }
 
     #[test]
-    fn parse_use() {
-        let src = "use foo/bar";
+    fn parse_try() {
+        let src = "try { throw x } catch err { err }";
         let ast = Parser::new(src)
             .produce_ast()
             .expect("should be able to parse ast");
 
         assert_eq!(
             ast,
-            vec![Stmt::Use(Use {
-                path: "foo/bar".to_string(),
-                span: (4, 7).into()
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Try {
+                    body: vec![Stmt::Expr(Expr {
+                        kind: ExprKind::Throw {
+                            value: Box::new(Expr {
+                                kind: ExprKind::Ident("x".to_string()),
+                                span: (12, 1).into()
+                            })
+                        },
+                        span: (6, 7).into()
+                    })],
+                    ident: Some("err".to_string()),
+                    catch: vec![Stmt::Expr(Expr {
+                        kind: ExprKind::Ident("err".to_string()),
+                        span: (28, 3).into()
+                    })]
+                },
+                span: (0, 33).into()
             })]
             .to_program()
-        )
+        );
     }
 
     #[test]
-    fn parse_loop() {
-        let src = "loop { break }";
+    fn parse_block() {
+        let src = "let foo = { 1 }";
         let ast = Parser::new(src)
             .produce_ast()
             .expect("should be able to parse ast");
 
         assert_eq!(
             ast,
-            vec![Stmt::Loop(Loop {
-                body: vec![Stmt::Expr(Expr {
-                    kind: ExprKind::Break,
-                    span: (7, 5).into()
-                })],
-                span: (0, 4).into()
+            vec![Stmt::Var(Var {
+                pattern: Pattern {
+                    kind: PatternKind::Binding("foo".to_string()),
+                    span: (4, 3).into()
+                },
+                value: Box::new(Stmt::Expr(Expr {
+                    kind: ExprKind::Block {
+                        body: vec![Stmt::Expr(Expr {
+                            kind: ExprKind::Int(1),
+                            span: (12, 1).into()
+                        })]
+                    },
+                    span: (10, 5).into()
+                }))
             })]
             .to_program()
         );
     }
 
     #[test]
-    fn parse_func() {
-        let src = "fn foo(bar) { return }";
+    fn parse_default_param() {
+        let src = "fn foo(bar = 1) {}";
         let ast = Parser::new(src)
             .produce_ast()
             .expect("should be able to parse ast");
@@ -804,11 +2985,17 @@ mod tests {
             ast,
             vec![Stmt::Func(Func {
                 ident: "foo".to_string(),
-                params: vec!["bar".to_string()],
-                body: vec![Stmt::Expr(Expr {
-                    kind: ExprKind::Return { value: None },
-                    span: (14, 6).into()
-                })],
+                params: vec![Param {
+                    pattern: Pattern {
+                        kind: PatternKind::Binding("bar".to_string()),
+                        span: (7, 3).into()
+                    },
+                    default: Some(Expr {
+                        kind: ExprKind::Int(1),
+                        span: (13, 1).into()
+                    })
+                }],
+                body: vec![],
                 span: (3, 3).into()
             })]
             .to_program()
@@ -816,57 +3003,164 @@ mod tests {
     }
 
     #[test]
-    fn parse_cond() {
-        let src = "if 1 < 2 { true }";
+    fn parse_named_arg() {
+        let src = "foo(1, bar = 2)";
         let ast = Parser::new(src)
             .produce_ast()
             .expect("should be able to parse ast");
 
         assert_eq!(
             ast,
-            vec![Stmt::Cond(Cond {
-                condition: Expr {
-                    kind: ExprKind::RelOp {
-                        left: Box::new(Expr {
-                            kind: ExprKind::Int(1),
-                            span: (3, 1).into()
-                        }),
-                        right: Box::new(Expr {
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Call {
+                    caller: Box::new(Expr {
+                        kind: ExprKind::Ident("foo".to_string()),
+                        span: (0, 3).into()
+                    }),
+                    args: vec![Expr {
+                        kind: ExprKind::Int(1),
+                        span: (4, 1).into()
+                    }],
+                    named: vec![(
+                        "bar".to_string(),
+                        Expr {
                             kind: ExprKind::Int(2),
-                            span: (7, 1).into()
-                        }),
-                        op: RelOp::Less
-                    },
-                    span: (3, 5).into()
+                            span: (13, 1).into()
+                        }
+                    )]
                 },
-                body: vec![Stmt::Expr(Expr {
-                    kind: ExprKind::Bool(true),
-                    span: (11, 4).into()
-                })],
-                span: (3, 5).into()
+                span: (0, 15).into()
             })]
             .to_program()
         );
     }
 
     #[test]
-    fn parse_var() {
-        let src = "let foo = 0";
+    fn parse_rest_param() {
+        let src = "fn log(level, ..parts) {}";
         let ast = Parser::new(src)
             .produce_ast()
             .expect("should be able to parse ast");
 
         assert_eq!(
             ast,
-            vec![Stmt::Var(Var {
-                ident: "foo".to_string(),
-                value: Box::new(Stmt::Expr(Expr {
-                    kind: ExprKind::Int(0),
-                    span: (10, 1).into()
-                })),
-                span: (4, 3).into()
+            vec![Stmt::Func(Func {
+                ident: "log".to_string(),
+                params: vec![
+                    Param {
+                        pattern: Pattern {
+                            kind: PatternKind::Binding("level".to_string()),
+                            span: (7, 5).into()
+                        },
+                        default: None
+                    },
+                    Param {
+                        pattern: Pattern {
+                            kind: PatternKind::Rest(Some("parts".to_string())),
+                            span: (14, 7).into()
+                        },
+                        default: None
+                    }
+                ],
+                body: vec![],
+                span: (3, 3).into()
+            })]
+            .to_program()
+        );
+    }
+
+    #[test]
+    fn parse_spread() {
+        let src = "foo(..bar, 1)";
+        let ast = Parser::new(src)
+            .produce_ast()
+            .expect("should be able to parse ast");
+
+        assert_eq!(
+            ast,
+            vec![Stmt::Expr(Expr {
+                kind: ExprKind::Call {
+                    caller: Box::new(Expr {
+                        kind: ExprKind::Ident("foo".to_string()),
+                        span: (0, 3).into()
+                    }),
+                    args: vec![
+                        Expr {
+                            kind: ExprKind::Spread {
+                                expr: Box::new(Expr {
+                                    kind: ExprKind::Ident("bar".to_string()),
+                                    span: (6, 3).into()
+                                })
+                            },
+                            span: (4, 5).into()
+                        },
+                        Expr {
+                            kind: ExprKind::Int(1),
+                            span: (11, 1).into()
+                        }
+                    ],
+                    named: vec![]
+                },
+                span: (0, 13).into()
             })]
             .to_program()
         );
     }
+
+    #[test]
+    fn parse_nesting_too_deep() {
+        let src = format!("{}1{}", "(".repeat(MAX_NESTING), ")".repeat(MAX_NESTING));
+        let error = Parser::new(&src)
+            .produce_ast()
+            .expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<ParserError>(),
+            Some(ParserError::NestingTooDeep { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_long_chain() {
+        let src = format!("1{}", " + 1".repeat(MAX_CHAIN));
+
+        // Chain links don't count towards the nesting depth, and cloning a long chain allocates
+        // more stack as it needs it
+        let result = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || {
+                let ast = Parser::new(&src).produce_ast()?;
+                Ok::<_, miette::Report>((ast.clone(), ast))
+            })
+            .expect("should be able to spawn thread")
+            .join()
+            .expect("cloning should not overflow the stack");
+
+        assert!(result.is_ok(), "should be able to parse a long chain");
+    }
+
+    #[test]
+    fn parse_chain_too_long() {
+        let src = format!("1{}", " + 1".repeat(MAX_CHAIN + 1));
+        let error = Parser::new(&src)
+            .produce_ast()
+            .expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<ParserError>(),
+            Some(ParserError::ChainTooLong { .. })
+        ));
+
+        // The links of enclosing chains count too, e.g. `1 + (1 + (1 + 1))` has 3 links
+        let chain = format!("1{} + (", " + 1".repeat(MAX_CHAIN / 100));
+        let src = format!("{}1{}", chain.repeat(100), ")".repeat(100));
+        let error = Parser::new(&src)
+            .produce_ast()
+            .expect_err("result should be an error");
+
+        assert!(matches!(
+            error.downcast_ref::<ParserError>(),
+            Some(ParserError::ChainTooLong { .. })
+        ));
+    }
 }
@@ -0,0 +1,16 @@
+//! Guards against overflowing the native stack when parsing and evaluating deeply nested programs,
+//! and when walking deeply nested values.
+
+/// The amount of stack that must remain before more is allocated.
+///
+/// It must cover the most stack used between two guarded points, including dropping a syntax tree
+/// of the maximum depth, which uses the most in unoptimized builds.
+const RED_ZONE: usize = 1024 * 1024;
+
+/// The amount of stack allocated whenever more is needed.
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Runs `f`, first switching to a newly allocated stack if the current one is close to running out.
+pub(crate) fn guard<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, STACK_SIZE, f)
+}
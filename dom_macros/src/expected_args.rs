@@ -58,7 +58,7 @@ pub(crate) fn expected_args_impl(args: TokenStream, input: TokenStream) -> Token
 
     let patterns_len = patterns.len();
     let patterns_combined = quote! {
-        [#(#patterns),*] = &args[..#patterns_len]
+        Some([#(#patterns),*]) = args.get(..#patterns_len)
     };
 
     let ItemFn {
@@ -84,7 +84,7 @@ pub(crate) fn expected_args_impl(args: TokenStream, input: TokenStream) -> Token
         // Reconstruct the function declaration
         #vis #sig {
             let #patterns_combined else {
-                return None;
+                return Some(Val::error(format!("invalid arguments passed to `{}`", self.name())));
             };
 
             // The rest of the function body
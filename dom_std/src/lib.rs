@@ -1,4 +1,5 @@
 mod list;
+mod map;
 mod str;
 
 use dom_core::{
@@ -27,7 +28,18 @@ impl ModuleHook for StdModule {
                     .register_builtin::<list::SetFn>("list")
                     .register_builtin::<list::PushFn>("list")
                     .register_builtin::<list::PopFn>("list")
-                    .register_builtin::<list::LenFn>("list");
+                    .register_builtin::<list::LenFn>("list")
+                    .register_builtin::<list::SliceFn>("list")
+                    .register_builtin::<list::CollectFn>("list");
+            }
+            Some("map") => {
+                env.register_builtin::<map::GetFn>("map")
+                    .register_builtin::<map::SetFn>("map")
+                    .register_builtin::<map::HasFn>("map")
+                    .register_builtin::<map::RemoveFn>("map")
+                    .register_builtin::<map::KeysFn>("map")
+                    .register_builtin::<map::ValuesFn>("map")
+                    .register_builtin::<map::LenFn>("map");
             }
             Some("str") => {
                 env.register_builtin::<str::LenFn>("str");
@@ -89,6 +89,51 @@ impl BuiltinFn for LenFn {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct SliceFn;
+
+impl BuiltinFn for SliceFn {
+    fn name(&self) -> &str {
+        "slice"
+    }
+
+    #[expected_args(List(list), Range(range))]
+    fn run(&self, args: &[Val], _: &Arc<Mutex<Env>>) -> Option<Val> {
+        let bounds = usize::try_from(range.start)
+            .ok()
+            .zip(usize::try_from(range.end).ok());
+
+        match bounds.and_then(|(start, end)| list.get(start..end)) {
+            Some(items) => Some(items.to_vec().into()),
+            None => Some(Val::error(format!(
+                "slice `{}..{}` is out of bounds for length `{}`",
+                range.start,
+                range.end,
+                list.len()
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CollectFn;
+
+impl BuiltinFn for CollectFn {
+    fn name(&self) -> &str {
+        "collect"
+    }
+
+    #[expected_args(Range(range))]
+    fn run(&self, args: &[Val], _: &Arc<Mutex<Env>>) -> Option<Val> {
+        let list = range
+            .clone()
+            .map(|int| ValKind::Int(int).into())
+            .collect::<Vec<Val>>();
+
+        Some(list.into())
+    }
+}
+
 trait Int32Ext {
     fn to_wrapped_index(&self, len: usize) -> usize;
 }
@@ -0,0 +1,118 @@
+use dom_macros::expected_args;
+
+use super::*;
+
+#[derive(Debug, Default)]
+pub struct GetFn;
+
+impl BuiltinFn for GetFn {
+    fn name(&self) -> &str {
+        "get"
+    }
+
+    #[expected_args(Map(map), Str(key))]
+    fn run(&self, args: &[Val], _: &Arc<Mutex<Env>>) -> Option<Val> {
+        map.get(key).cloned()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SetFn;
+
+impl BuiltinFn for SetFn {
+    fn name(&self) -> &str {
+        "set"
+    }
+
+    #[expected_args(Map(map), Str(key), Val(value))]
+    fn run(&self, args: &[Val], _: &Arc<Mutex<Env>>) -> Option<Val> {
+        let mut map = map.clone();
+
+        map.insert(key.clone(), value.clone());
+
+        Some(map.into())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HasFn;
+
+impl BuiltinFn for HasFn {
+    fn name(&self) -> &str {
+        "has"
+    }
+
+    #[expected_args(Map(map), Str(key))]
+    fn run(&self, args: &[Val], _: &Arc<Mutex<Env>>) -> Option<Val> {
+        Some(ValKind::Bool(map.contains_key(key)).into())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RemoveFn;
+
+impl BuiltinFn for RemoveFn {
+    fn name(&self) -> &str {
+        "remove"
+    }
+
+    #[expected_args(Map(map), Str(key))]
+    fn run(&self, args: &[Val], _: &Arc<Mutex<Env>>) -> Option<Val> {
+        let mut map = map.clone();
+
+        map.remove(key);
+
+        Some(map.into())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct KeysFn;
+
+impl BuiltinFn for KeysFn {
+    fn name(&self) -> &str {
+        "keys"
+    }
+
+    #[expected_args(Map(map))]
+    fn run(&self, args: &[Val], _: &Arc<Mutex<Env>>) -> Option<Val> {
+        let keys = map
+            .keys()
+            .map(|key| ValKind::Str(key.clone()).into())
+            .collect::<Vec<Val>>();
+
+        Some(keys.into())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ValuesFn;
+
+impl BuiltinFn for ValuesFn {
+    fn name(&self) -> &str {
+        "values"
+    }
+
+    #[expected_args(Map(map))]
+    fn run(&self, args: &[Val], _: &Arc<Mutex<Env>>) -> Option<Val> {
+        let values = map.values().cloned().collect::<Vec<Val>>();
+
+        Some(values.into())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LenFn;
+
+impl BuiltinFn for LenFn {
+    fn name(&self) -> &str {
+        "len"
+    }
+
+    #[expected_args(Map(map))]
+    fn run(&self, args: &[Val], _: &Arc<Mutex<Env>>) -> Option<Val> {
+        let len = map.len();
+
+        Some(ValKind::Int(len as i32).into())
+    }
+}
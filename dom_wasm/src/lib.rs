@@ -1,6 +1,10 @@
 mod hooks;
 
-use dom_core::{environment::Env, interpreter::Interpreter, parser::Parser};
+use dom_core::{
+    environment::Env,
+    interpreter::{Interpreter, DEFAULT_MAX_DEPTH},
+    parser::Parser,
+};
 
 use wasm_bindgen::prelude::*;
 use web_sys::console;
@@ -26,6 +30,12 @@ pub fn init_miette_hook() {
 
 #[wasm_bindgen]
 pub fn interpret(source: &str) -> String {
+    interpret_with_max_depth(source, DEFAULT_MAX_DEPTH)
+}
+
+/// Like [`interpret`], with a maximum depth of nested function calls of `max_depth`.
+#[wasm_bindgen]
+pub fn interpret_with_max_depth(source: &str, max_depth: usize) -> String {
     let env = Env::new();
 
     let (ast, program) = match Parser::new(source).produce_ast() {
@@ -37,8 +47,9 @@ pub fn interpret(source: &str) -> String {
         }
     };
 
-    if let Err(error) =
-        Interpreter::new::<hooks::WasmUseHook, hooks::WasmModuleHook>().eval(program, &env)
+    if let Err(error) = Interpreter::new::<hooks::WasmUseHook, hooks::WasmModuleHook>()
+        .with_max_depth(max_depth)
+        .eval(program, &env)
     {
         let error = error.with_source_code(source.to_string());
         console::log_1(&format!("{error:?}").into());